@@ -1,9 +1,9 @@
 use std::io;
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::ops::Deref;
 
-use crate::Sayer;
-use crate::util::{big_end_first_2, big_end_first_4, big_end_first_8, io_error_of_utf8, u16_of_buf, U32x2, u32x2_of_buf, u64_of_buf};
+use crate::{ObjectId, Point, Say, Sayer, Target};
+use crate::util::{big_end_first_2, big_end_first_4, big_end_first_8, io_error_of_utf8, u16_of_buf, u32_of_buf, U32x2, u32x2_of_buf, u64_of_buf};
 
 pub trait WriteBytes {
 	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize>;
@@ -13,12 +13,17 @@ pub trait ReadBytes<T> {
 	fn read_bytes(reader: &mut impl Read) -> io::Result<T>;
 }
 
+/// Reports a tag that names no variant of the kind being read.
+pub(crate) fn unknown_tag(kind: &str, tag: u8) -> io::Error {
+	io::Error::new(ErrorKind::InvalidData, format!("Unknown {} tag {}", kind, tag))
+}
+
 impl ReadBytes<Sayer> for Sayer {
 	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
 		match u8::read_bytes(reader)? {
 			0 => Ok(Sayer::Unit),
 			1 => Ok(Sayer::Named(String::read_bytes(reader)?)),
-			_ => unimplemented!()
+			tag => Err(unknown_tag("sayer", tag)),
 		}
 	}
 }
@@ -39,6 +44,26 @@ impl WriteBytes for Sayer {
 	}
 }
 
+impl ReadBytes<Say> for Say {
+	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
+		let sayer = Sayer::read_bytes(reader)?;
+		let object = ObjectId::read_bytes(reader)?;
+		let point = Point::read_bytes(reader)?;
+		let target = Option::<Target>::read_bytes(reader)?;
+		Ok(Say { sayer, object, point, target })
+	}
+}
+
+impl WriteBytes for Say {
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		let sayer_size = self.sayer.write_bytes(writer)?;
+		let object_size = self.object.write_bytes(writer)?;
+		let point_size = self.point.write_bytes(writer)?;
+		let target_size = self.target.write_bytes(writer)?;
+		Ok(sayer_size + object_size + point_size + target_size)
+	}
+}
+
 impl ReadBytes<String> for String {
 	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
 		let byte_count = u16::read_bytes(reader)?;
@@ -93,6 +118,15 @@ impl ReadBytes<u16> for u16 {
 	}
 }
 
+impl WriteBytes for u16 {
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		let mut bytes = [0u8; 2];
+		big_end_first_2(*self, &mut bytes);
+		writer.write_all(&bytes)?;
+		Ok(bytes.len())
+	}
+}
+
 impl ReadBytes<U32x2> for U32x2 {
 	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
 		let buf = &mut [0u8; 8];
@@ -101,6 +135,14 @@ impl ReadBytes<U32x2> for U32x2 {
 	}
 }
 
+impl ReadBytes<u32> for u32 {
+	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
+		let buf = &mut [0u8; 4];
+		reader.read_exact(buf)?;
+		Ok(u32_of_buf(buf))
+	}
+}
+
 impl WriteBytes for u32 {
 	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
 		let mut bytes = [0u8; 4];
@@ -1,18 +1,27 @@
 use std::collections::HashMap;
 use std::io;
 
-use crate::{diary, ObjectId, Point, Target};
-use crate::hamt::{Hamt, ProdAB, Reader, Root};
+use crate::{diary, EchoError, ObjectId, Point, Say, Sayer, Target};
+use crate::echo::RootsReader;
+use crate::hamt::{Hamt, Reader, Root};
 
+/// `Chamber` reads the facts of one commit. Clones share the diary's open file, and a
+/// chamber may be shared across threads.
+#[derive(Debug, Clone)]
 pub struct Chamber {
 	pub(crate) object_points_reader: Reader,
 	pub(crate) point_objects_reader: Reader,
 	pub(crate) diary_reader: diary::Reader,
+	pub(crate) roots_reader: RootsReader,
+	pub(crate) version: u64,
 }
 
 
 impl Chamber {
-	pub fn objects<'a, F: ObjectFilter<'a>>(&mut self) -> io::Result<Vec<F>> {
+	/// The version of the commit whose facts the chamber reads, or zero before the first commit.
+	pub fn version(&self) -> u64 { self.version }
+
+	pub fn objects<'a, F: ObjectFilter<'a>>(&mut self) -> Result<Vec<F>, EchoError> {
 		let obj_names = self.objects_with_point(F::key_point())?;
 		let objects = obj_names.into_iter()
 			.map(|obj_name| {
@@ -22,19 +31,19 @@ impl Chamber {
 		Ok(objects)
 	}
 
-	pub fn objects_with_property(&self, point: &Point, target: &Target) -> io::Result<Vec<ObjectId>> {
+	pub fn objects_with_property(&self, point: &Point, target: &Target) -> Result<Vec<ObjectId>, EchoError> {
 		let mut matching_objects = Vec::new();
 		for object in self.objects_with_point(point)? {
-			if target.eq(&self.read_target(&object, point)?.unwrap()) {
+			if self.read_target(&object, point)?.as_ref() == Some(target) {
 				matching_objects.push(object)
 			}
 		}
 		Ok(matching_objects)
 	}
 
-	pub fn objects_with_point(&self, point: &Point) -> io::Result<Vec<ObjectId>> {
+	pub fn objects_with_point(&self, point: &Point) -> Result<Vec<ObjectId>, EchoError> {
 		let mut diary_reader = self.diary_reader.clone();
-		self.inner_objects_with_point(point, &mut diary_reader)
+		Ok(self.inner_objects_with_point(point, &mut diary_reader)?)
 	}
 
 	fn inner_objects_with_point(&self, point: &Point, reader: &mut diary::Reader) -> io::Result<Vec<ObjectId>> {
@@ -42,9 +51,9 @@ impl Chamber {
 		let objects = match objects_root {
 			None => Vec::new(),
 			Some(root) => {
-				let object_target_reader = Hamt::new(root).reader()?;
-				let object_target = object_target_reader.read_all::<ProdAB<ObjectId, Target>>(reader)?;
-				object_target.into_iter().map(|it| it.a).collect()
+				let object_says_reader = Hamt::new(root).reader()?;
+				let object_says = object_says_reader.read_all::<Say>(reader)?;
+				object_says.into_iter().map(|it| it.object).collect()
 			}
 		};
 		Ok(objects)
@@ -61,16 +70,31 @@ impl Chamber {
 		self.object_properties(&ObjectId::Unit, points)
 	}
 
+	/// Reads the text at a point on an object. Panics where `try_string` fails.
 	pub fn string(&self, object: &ObjectId, point: &Point) -> String {
-		self.target_at_object_point(object, point).as_str().to_string()
+		self.try_string(object, point).unwrap_or_else(|e| panic!("{}", e))
 	}
 
+	/// Reads the number at a point on an object. Panics where `try_number` fails.
 	pub fn number(&self, object: &ObjectId, point: &Point) -> u64 {
-		self.target_at_object_point(object, point).as_number()
+		self.try_number(object, point).unwrap_or_else(|e| panic!("{}", e))
 	}
 
+	/// Reads the object id at a point on an object. Panics where `try_object_id` fails.
 	pub fn object_id(&self, object: &ObjectId, point: &Point) -> ObjectId {
-		self.target_at_object_point(object, point).as_object_id().to_owned()
+		self.try_object_id(object, point).unwrap_or_else(|e| panic!("{}", e))
+	}
+
+	pub fn try_string(&self, object: &ObjectId, point: &Point) -> Result<String, EchoError> {
+		Ok(self.try_target_at_object_point(object, point)?.try_as_str()?.to_string())
+	}
+
+	pub fn try_number(&self, object: &ObjectId, point: &Point) -> Result<u64, EchoError> {
+		self.try_target_at_object_point(object, point)?.try_as_number()
+	}
+
+	pub fn try_object_id(&self, object: &ObjectId, point: &Point) -> Result<ObjectId, EchoError> {
+		Ok(self.try_target_at_object_point(object, point)?.try_as_object_id()?.to_owned())
 	}
 
 	pub fn targets_at_object_points(&self, object: &ObjectId, points: Vec<&Point>) -> HashMap<Point, Target> {
@@ -84,30 +108,105 @@ impl Chamber {
 	}
 
 	pub fn target_at_object_point(&self, object: &ObjectId, point: &Point) -> Target {
-		let option = self.target_at_object_point_or_none(object, point);
-		option.unwrap()
+		//! Acquire the target at a point on an object. Panics where `try_target_at_object_point` fails.
+		self.try_target_at_object_point(object, point).unwrap_or_else(|e| panic!("{}", e))
 	}
 
 	pub fn target_at_object_point_or_none(&self, object: &ObjectId, point: &Point) -> Option<Target> {
-		//! Acquire some target at a point on an object or nothing.
-		let option = self.read_target(object, point).unwrap();
-		option
+		//! Acquire some target at a point on an object or nothing. Panics where `try_target_at_object_point_or_none` fails.
+		self.try_target_at_object_point_or_none(object, point).unwrap_or_else(|e| panic!("{}", e))
 	}
 
 	pub fn target_or_none(&mut self) -> Option<Target> {
 		self.target_at_object_point_or_none(&ObjectId::Unit, &Point::Unit)
 	}
 
-	fn read_target(&self, object: &ObjectId, point: &Point) -> io::Result<Option<Target>> {
+	pub fn try_target_at_object_point(&self, object: &ObjectId, point: &Point) -> Result<Target, EchoError> {
+		//! Acquire the target at a point on an object, failing when there is none.
+		self.try_target_at_object_point_or_none(object, point)?.ok_or_else(|| EchoError::MissingValue {
+			object: object.to_owned(),
+			point: point.to_owned(),
+		})
+	}
+
+	pub fn try_target_at_object_point_or_none(&self, object: &ObjectId, point: &Point) -> Result<Option<Target>, EchoError> {
+		//! Acquire some target at a point on an object or nothing.
+		Ok(self.read_target(object, point)?)
+	}
+
+	pub fn try_target_or_none(&mut self) -> Result<Option<Target>, EchoError> {
+		self.try_target_at_object_point_or_none(&ObjectId::Unit, &Point::Unit)
+	}
+
+	/// Lists each change to the target at a point on an object up to the chamber's
+	/// version, newest first. Each change holds the version of the commit that made it
+	/// and the new target, or `None` where the commit retracted the target.
+	pub fn history(&self, object: &ObjectId, point: &Point) -> Result<Vec<(u64, Option<Target>)>, EchoError> {
 		let mut reader = self.diary_reader.clone();
-		let root: Option<Root> = self.object_points_reader.read_value(object, &mut reader)?;
-		match root {
-			None => Ok(None),
-			Some(root) => {
-				let point_targets = Hamt::new(root);
-				point_targets.reader()?.read_value(point, &mut reader)
+		let mut changes = Vec::new();
+		let mut newer: Option<(u64, Option<Target>)> = None;
+		let mut last_object_points_root = None;
+		let mut last_point_targets_root = None;
+		let mut target = None;
+		let commits = self.roots_reader.read_commits(1..=self.version)?;
+		for (index, commit) in commits.into_iter().enumerate().rev() {
+			let version = index as u64 + 1;
+			let object_points_root = commit.roots.0;
+			if last_object_points_root != Some(object_points_root) {
+				let point_targets_root: Option<Root> = Reader::new(object_points_root).read_value(object, &mut reader)?;
+				if last_point_targets_root != Some(point_targets_root) {
+					target = read_point_say(point_targets_root, point, &mut reader)?.and_then(|it| it.target);
+					last_point_targets_root = Some(point_targets_root);
+				}
+				last_object_points_root = Some(object_points_root);
 			}
+			if let Some((newer_version, newer_target)) = newer.take() {
+				if newer_target != target {
+					changes.push((newer_version, newer_target));
+				}
+			}
+			newer = Some((version, target.to_owned()));
+		}
+		if let Some((oldest_version, Some(oldest_target))) = newer {
+			changes.push((oldest_version, Some(oldest_target)));
 		}
+		Ok(changes)
+	}
+
+	/// Acquire the sayer of the target at a point on an object, or nothing when the point holds no target.
+	pub fn sayer_at_object_point(&self, object: &ObjectId, point: &Point) -> Result<Option<Sayer>, EchoError> {
+		let say = self.read_say(object, point)?;
+		Ok(say.map(|it| it.sayer))
+	}
+
+	/// Lists the says of the given sayer that are current in the chamber. Every object in the
+	/// chamber is visited.
+	pub fn says_by(&self, sayer: &Sayer) -> Result<Vec<Say>, EchoError> {
+		let mut reader = self.diary_reader.clone();
+		let mut says = Vec::new();
+		for point_says_root in self.object_points_reader.read_all::<Root>(&mut reader)? {
+			let point_says = Hamt::new(point_says_root).reader()?.read_all::<Say>(&mut reader)?;
+			says.extend(point_says.into_iter().filter(|say| &say.sayer == sayer));
+		}
+		Ok(says)
+	}
+
+	pub(crate) fn read_target(&self, object: &ObjectId, point: &Point) -> io::Result<Option<Target>> {
+		let say = self.read_say(object, point)?;
+		Ok(say.and_then(|it| it.target))
+	}
+
+	fn read_say(&self, object: &ObjectId, point: &Point) -> io::Result<Option<Say>> {
+		let mut reader = self.diary_reader.clone();
+		let root: Option<Root> = self.object_points_reader.read_value(object, &mut reader)?;
+		read_point_say(root, point, &mut reader)
+	}
+}
+
+fn read_point_say(point_says_root: Option<Root>, point: &Point, reader: &mut diary::Reader) -> io::Result<Option<Say>> {
+	match point_says_root {
+		None => Ok(None),
+		Some(root) => Hamt::new(root).reader()?.read_value(point, reader),
 	}
 }
 
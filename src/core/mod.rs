@@ -1,12 +1,19 @@
+use std::fmt;
 use std::hash::Hash;
 
+use crate::EchoError;
+
 pub use object::*;
 pub use point::*;
-pub use target::*;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) struct Speech {
-	pub says: Vec<Say>
+	pub sayer: Sayer,
+	pub says: Vec<Say>,
+	pub retracted_objects: Vec<ObjectId>,
+	pub metadata: Vec<(Point, Target)>,
+	/// Writes the says through the bulk loader instead of transient maps.
+	pub bulk: bool,
 }
 
 pub trait Writable {
@@ -59,11 +66,39 @@ impl Target {
 		}
 	}
 
-	pub fn to_string(&self) -> String {
+	pub fn try_as_object_id(&self) -> Result<&ObjectId, EchoError> {
+		match self {
+			Target::Object(id) => Ok(id),
+			_ => Err(self.mismatch("an object")),
+		}
+	}
+
+	pub fn try_as_number(&self) -> Result<u64, EchoError> {
+		match self {
+			Target::Number(n) => Ok(*n),
+			_ => Err(self.mismatch("a number")),
+		}
+	}
+
+	pub fn try_as_str(&self) -> Result<&str, EchoError> {
+		match self {
+			Target::String(s) => Ok(s),
+			_ => Err(self.mismatch("text")),
+		}
+	}
+
+	fn mismatch(&self, expected: &'static str) -> EchoError {
+		EchoError::TypeMismatch { expected, target: self.to_owned() }
+	}
+
+}
+
+impl fmt::Display for Target {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
-			Target::Number(n) => format!("{}", n),
-			Target::String(s) => s.to_string(),
-			Target::Object(object_id) => format!("{:?}", object_id),
+			Target::Number(n) => write!(f, "{}", n),
+			Target::String(s) => write!(f, "{}", s),
+			Target::Object(object_id) => write!(f, "{:?}", object_id),
 		}
 	}
 }
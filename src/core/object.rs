@@ -1,7 +1,7 @@
 use std::io::{Read, Write};
 use std::io;
 
-use crate::bytes::{ReadBytes, WriteBytes};
+use crate::bytes::{ReadBytes, unknown_tag, WriteBytes};
 use crate::hamt::Key;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
@@ -26,7 +26,7 @@ impl ReadBytes<ObjectId> for ObjectId {
 				let name = String::read_bytes(reader)?;
 				Ok(ObjectId::String(name))
 			}
-			_ => unimplemented!()
+			tag => Err(unknown_tag("object", tag)),
 		}
 	}
 }
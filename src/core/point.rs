@@ -1,11 +1,34 @@
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
 use std::io;
 
-use crate::bytes::{ReadBytes, WriteBytes};
+use crate::bytes::{ReadBytes, unknown_tag, WriteBytes};
 use crate::hamt::Key;
 
-#[derive(Debug, Clone, Eq, Hash)]
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod tests {
+	use crate::bytes::{ReadBytes, WriteBytes};
+	use crate::hamt::Key;
+	use crate::Point;
+
+	#[test]
+	fn static_points_hash_and_write_like_string_points() {
+		let static_point = Point::Static { name: "count", aspect: "Counter" };
+		let string_point = Point::from(("count", "Counter"));
+		assert_eq!(static_point.universal(1), string_point.universal(1));
+		let (mut static_bytes, mut string_bytes) = (Vec::new(), Vec::new());
+		static_point.write_bytes(&mut static_bytes).unwrap();
+		string_point.write_bytes(&mut string_bytes).unwrap();
+		assert_eq!(static_bytes, string_bytes);
+		let read_point = Point::read_bytes(&mut &static_bytes[..]).unwrap();
+		assert_eq!(read_point, static_point);
+	}
+}
+
+#[derive(Debug, Clone, Eq, Default)]
 pub enum Point {
+	#[default]
 	Unit,
 	String { aspect: String, name: String },
 	Static { aspect: &'static str, name: &'static str },
@@ -13,8 +36,24 @@ pub enum Point {
 
 impl Key for Point {}
 
-impl Default for Point {
-	fn default() -> Self { Point::Unit }
+impl Hash for Point {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		// Static and String points are equal when their names and aspects match, and points
+		// read back from the diary are always String points, so both must hash alike.
+		match self {
+			Point::Unit => state.write_u8(0),
+			Point::String { name, aspect } => {
+				state.write_u8(1);
+				name.as_str().hash(state);
+				aspect.as_str().hash(state);
+			}
+			Point::Static { name, aspect } => {
+				state.write_u8(1);
+				name.hash(state);
+				aspect.hash(state);
+			}
+		}
+	}
 }
 
 impl<S: AsRef<str>> From<(S, S)> for Point {
@@ -35,7 +74,7 @@ impl ReadBytes<Point> for Point {
 				let aspect = String::read_bytes(reader)?;
 				Ok(Point::String { name, aspect })
 			}
-			_ => unimplemented!()
+			tag => Err(unknown_tag("point", tag)),
 		}
 	}
 }
@@ -54,7 +93,8 @@ impl WriteBytes for Point {
 				Ok(1 + name_len + aspect_len)
 			}
 			Point::Static { name, aspect } => {
-				writer.write_all(&[2])?;
+				// Static points are written as String points so equal points have equal key bytes.
+				writer.write_all(&[1])?;
 				let name_len = name.write_bytes(writer)?;
 				let aspect_len = aspect.write_bytes(writer)?;
 				Ok(1 + name_len + aspect_len)
@@ -2,11 +2,12 @@ use std::io::{Read, Write};
 use std::io;
 
 use crate::{ObjectId, Target};
-use crate::bytes::{ReadBytes, WriteBytes};
+use crate::bytes::{ReadBytes, unknown_tag, WriteBytes};
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
-	use std::io::Cursor;
+	use std::io::{Cursor, ErrorKind};
 
 	use crate::bytes::{ReadBytes, WriteBytes};
 	use crate::Target;
@@ -20,6 +21,33 @@ mod tests {
 		let target_final = Target::read_bytes(&mut cursor).unwrap();
 		assert_eq!(target_final, target);
 	}
+
+	#[test]
+	fn number() {
+		let target = Target::Number(u64::MAX - 1);
+		let mut cursor = Cursor::new(Vec::new());
+		target.write_bytes(&mut cursor).unwrap();
+		cursor.set_position(0);
+		let target_final = Target::read_bytes(&mut cursor).unwrap();
+		assert_eq!(target_final, target);
+	}
+
+	#[test]
+	fn retraction() {
+		let mut cursor = Cursor::new(Vec::new());
+		let size = None::<Target>.write_bytes(&mut cursor).unwrap();
+		assert_eq!(size, 1);
+		cursor.set_position(0);
+		let target_final = Option::<Target>::read_bytes(&mut cursor).unwrap();
+		assert_eq!(target_final, None);
+	}
+
+	#[test]
+	fn unknown_tag() {
+		let mut cursor = Cursor::new(vec![9]);
+		let error = Option::<Target>::read_bytes(&mut cursor).unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::InvalidData);
+	}
 }
 
 impl ReadBytes<Target> for Target {
@@ -37,7 +65,7 @@ impl ReadBytes<Target> for Target {
 				let object_id = ObjectId::read_bytes(reader)?;
 				Ok(Target::Object(object_id))
 			}
-			_ => unimplemented!()
+			tag => Err(unknown_tag("target", tag)),
 		}
 	}
 }
@@ -63,4 +91,27 @@ impl WriteBytes for Target {
 	}
 }
 
+impl ReadBytes<Option<Target>> for Option<Target> {
+	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
+		match u8::read_bytes(reader)? {
+			0 => Ok(None),
+			1 => Ok(Some(Target::Number(u64::read_bytes(reader)?))),
+			2 => Ok(Some(Target::String(String::read_bytes(reader)?))),
+			3 => Ok(Some(Target::Object(ObjectId::read_bytes(reader)?))),
+			tag => Err(unknown_tag("target", tag)),
+		}
+	}
+}
 
+impl WriteBytes for Option<Target> {
+	/// Writes a retraction as a lone zero tag.
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		match self {
+			None => {
+				writer.write_all(&[0])?;
+				Ok(1)
+			}
+			Some(target) => target.write_bytes(writer),
+		}
+	}
+}
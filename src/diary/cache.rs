@@ -0,0 +1,168 @@
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::hamt::slot::Slot;
+
+#[cfg(test)]
+mod tests {
+	use crate::diary::Cache;
+
+	#[test]
+	fn evicts_least_recently_used() {
+		let cache = Cache::new(0, 2);
+		assert_eq!(cache.value(1, || Ok(10u32)).unwrap(), 10);
+		assert_eq!(cache.value(2, || Ok(20u32)).unwrap(), 20);
+		assert_eq!(cache.value(1, || Ok(11u32)).unwrap(), 10);
+		assert_eq!(cache.value(3, || Ok(30u32)).unwrap(), 30);
+		assert_eq!(cache.value(2, || Ok(21u32)).unwrap(), 21);
+		assert_eq!(cache.value(1, || Ok(12u32)).unwrap(), 12);
+		let stats = cache.stats();
+		assert_eq!((stats.value_hits, stats.value_misses), (1, 5));
+	}
+
+	#[test]
+	fn forgets_positions_from_end() {
+		let cache = Cache::new(0, 4);
+		cache.value(1, || Ok(10u32)).unwrap();
+		cache.value(8, || Ok(80u32)).unwrap();
+		cache.forget_from(8);
+		assert_eq!(cache.value(1, || Ok(11u32)).unwrap(), 10);
+		assert_eq!(cache.value(8, || Ok(81u32)).unwrap(), 81);
+	}
+}
+
+/// `Cache` holds decoded HAMT frames and decoded diary values by their diary position.
+/// Positions below the committed diary length never change, so entries stay valid until
+/// a rollback drops the bytes at their positions. Each kind of entry is limited to a
+/// count and the least recently used entry is evicted first.
+#[derive(Debug)]
+pub struct Cache {
+	frames: Mutex<Lru<Arc<[Slot]>>>,
+	values: Mutex<Lru<Arc<dyn Any + Send + Sync>>>,
+	frame_hits: AtomicU64,
+	frame_misses: AtomicU64,
+	value_hits: AtomicU64,
+	value_misses: AtomicU64,
+}
+
+/// Hit and miss counts of a `Cache`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct CacheStats {
+	pub frame_hits: u64,
+	pub frame_misses: u64,
+	pub value_hits: u64,
+	pub value_misses: u64,
+}
+
+impl Cache {
+	pub fn stats(&self) -> CacheStats {
+		CacheStats {
+			frame_hits: self.frame_hits.load(Ordering::Relaxed),
+			frame_misses: self.frame_misses.load(Ordering::Relaxed),
+			value_hits: self.value_hits.load(Ordering::Relaxed),
+			value_misses: self.value_misses.load(Ordering::Relaxed),
+		}
+	}
+
+	/// Finds the slots of the frame at a position, reading them on a miss.
+	pub(crate) fn frame(&self, pos: usize, read: impl FnOnce() -> io::Result<Vec<Slot>>) -> io::Result<Arc<[Slot]>> {
+		if let Some(frame) = lock(&self.frames).get(pos) {
+			self.frame_hits.fetch_add(1, Ordering::Relaxed);
+			return Ok(frame);
+		}
+		self.frame_misses.fetch_add(1, Ordering::Relaxed);
+		let frame: Arc<[Slot]> = read()?.into();
+		lock(&self.frames).insert(pos, frame.clone());
+		Ok(frame)
+	}
+
+	/// Finds the value at a position, reading it on a miss.
+	pub(crate) fn value<V: Clone + Send + Sync + 'static>(&self, pos: usize, read: impl FnOnce() -> io::Result<V>) -> io::Result<V> {
+		let cached = lock(&self.values).get(pos);
+		if let Some(value) = cached.as_ref().and_then(|it| it.downcast_ref::<V>()) {
+			self.value_hits.fetch_add(1, Ordering::Relaxed);
+			return Ok(value.clone());
+		}
+		self.value_misses.fetch_add(1, Ordering::Relaxed);
+		let value = read()?;
+		lock(&self.values).insert(pos, Arc::new(value.clone()));
+		Ok(value)
+	}
+
+	/// Drops the entries at or after a position.
+	pub(crate) fn forget_from(&self, pos: usize) {
+		lock(&self.frames).forget_from(pos);
+		lock(&self.values).forget_from(pos);
+	}
+
+	/// Constructs a cache holding up to the given counts of frames and values.
+	pub fn new(frame_capacity: usize, value_capacity: usize) -> Self {
+		Cache {
+			frames: Mutex::new(Lru::new(frame_capacity)),
+			values: Mutex::new(Lru::new(value_capacity)),
+			frame_hits: AtomicU64::new(0),
+			frame_misses: AtomicU64::new(0),
+			value_hits: AtomicU64::new(0),
+			value_misses: AtomicU64::new(0),
+		}
+	}
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+	mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Least-recently-used map from positions to values. Each use stamps an entry with the
+/// next tick, and `order` finds the entry with the oldest tick.
+#[derive(Debug)]
+struct Lru<V> {
+	capacity: usize,
+	tick: u64,
+	entries: HashMap<usize, (V, u64)>,
+	order: BTreeMap<u64, usize>,
+}
+
+impl<V: Clone> Lru<V> {
+	fn get(&mut self, pos: usize) -> Option<V> {
+		self.tick += 1;
+		let (value, used) = self.entries.get_mut(&pos)?;
+		self.order.remove(used);
+		*used = self.tick;
+		self.order.insert(self.tick, pos);
+		Some(value.clone())
+	}
+
+	fn insert(&mut self, pos: usize, value: V) {
+		if self.capacity == 0 {
+			return;
+		}
+		self.tick += 1;
+		if let Some((_, used)) = self.entries.insert(pos, (value, self.tick)) {
+			self.order.remove(&used);
+		}
+		self.order.insert(self.tick, pos);
+		while self.entries.len() > self.capacity {
+			if let Some((_, oldest)) = self.order.pop_first() {
+				self.entries.remove(&oldest);
+			}
+		}
+	}
+
+	fn forget_from(&mut self, pos: usize) {
+		let order = &mut self.order;
+		self.entries.retain(|entry_pos, (_, used)| {
+			let keep = *entry_pos < pos;
+			if !keep {
+				order.remove(used);
+			}
+			keep
+		});
+	}
+
+	fn new(capacity: usize) -> Self {
+		Lru { capacity, tick: 0, entries: HashMap::new(), order: BTreeMap::new() }
+	}
+}
@@ -3,8 +3,9 @@ use std::error::Error;
 use std::fs::OpenOptions;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::diary::{Reader, Writer};
+use crate::diary::{Cache, Reader, Writer};
 use crate::util;
 
 /// `Diary` holds a file path and a virtual length for the file. Bytes
@@ -12,6 +13,7 @@ use crate::util;
 pub struct Diary {
 	pub file_path: PathBuf,
 	pub file_size: Cell<usize>,
+	reader: Reader,
 }
 
 impl Diary {
@@ -20,14 +22,16 @@ impl Diary {
 	pub fn load(file_path: &Path) -> io::Result<Diary> {
 		let file_path = file_path.to_path_buf();
 		let file_size = {
-			let file = OpenOptions::new().write(true).create(true).open(&file_path)?;
+			let file = OpenOptions::new().write(true).create(true).truncate(false).open(&file_path)?;
 			Cell::new(file.metadata()?.len() as usize)
 		};
-		Ok(Diary { file_path, file_size })
+		let reader = Reader::new(&file_path, file_size.get())?;
+		Ok(Diary { file_path, file_size, reader })
 	}
-	/// Opens a file reader at the diary's path using the diary's current length as the length of the file.
+	/// Constructs a reader sharing the diary's open file and using the diary's current
+	/// length as the length of the file.
 	pub fn reader(&self) -> io::Result<Reader> {
-		Reader::new(&self.file_path, self.file_size.get())
+		Ok(self.reader.with_size(self.file_size.get()))
 	}
 
 	/// Opens a file writer at the diary's path using the diary's current length as the starting
@@ -35,6 +39,14 @@ impl Diary {
 	pub fn writer(&self) -> io::Result<Writer> {
 		Writer::new(&self.file_path, self.file_size.get())
 	}
+	/// Shares the cache with the diary's readers.
+	pub fn with_cache(mut self, cache: Arc<Cache>) -> Diary {
+		self.reader = self.reader.with_cache(cache);
+		self
+	}
+
+	pub fn cache(&self) -> Option<&Arc<Cache>> { self.reader.cache() }
+
 	/// Allows a writer to change the diary's virtual length to include new bytes.
 	pub fn commit(&self, size: usize) {
 		self.file_size.set(size);
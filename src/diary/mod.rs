@@ -1,10 +1,13 @@
+pub use self::cache::{Cache, CacheStats};
 pub use self::diary::Diary;
 pub use self::pos::*;
 pub use self::reader::Reader;
 pub use self::writer::Writer;
 
+mod cache;
 mod writer;
 mod reader;
+#[allow(clippy::module_inception)]
 mod diary;
 mod pos;
 
@@ -1,39 +1,193 @@
+use std::borrow::Cow;
+use std::cmp::min;
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::{Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
 
-use crate::{diary, ObjectId, Point, Say, Target};
+use memmap2::Mmap;
+
+use crate::{diary, Say};
 use crate::bytes::ReadBytes;
-use crate::Sayer;
+use crate::diary::Cache;
+
+#[cfg(test)]
+mod tests {
+	use crate::diary::Diary;
+
+	#[test]
+	fn reads_within_file_size_as_diary_grows() {
+		let diary = Diary::temp().unwrap();
+		let mut writer = diary.writer().unwrap();
+		let (first_pos, _) = writer.write(&7u32).unwrap();
+		let reader = writer.reader().unwrap();
+		let (second_pos, _) = writer.write(&9u32).unwrap();
+		assert_eq!(reader.read::<u32>(first_pos).unwrap(), 7);
+		assert!(reader.read::<u32>(second_pos).is_err());
+		let grown = reader.with_size(writer.end_size());
+		assert_eq!(grown.read::<u32>(second_pos).unwrap(), 9);
+	}
+
+	#[test]
+	fn reads_exact_bytes_within_file_size() {
+		let diary = Diary::temp().unwrap();
+		let mut writer = diary.writer().unwrap();
+		let (pos, _) = writer.write(&0x01020304u32).unwrap();
+		writer.write(&0x05060708u32).unwrap();
+		let reader = writer.reader().unwrap();
+		assert_eq!(&reader.read_exact(pos, 8).unwrap()[..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+		assert!(reader.read_exact(pos, 9).is_err());
+		let file_reader = crate::diary::Reader::new(&diary.file_path, 4).unwrap();
+		assert_eq!(&file_reader.read_exact(pos, 4).unwrap()[..], &[1, 2, 3, 4]);
+		assert!(file_reader.read_exact(pos, 8).is_err());
+	}
+
+	#[test]
+	fn mapped_bytes_survive_truncating_unsealed_bytes() {
+		let diary = Diary::temp().unwrap();
+		let mut writer = diary.writer().unwrap();
+		let (pos, _) = writer.write(&7u32).unwrap();
+		writer.seal(writer.end_size());
+		let reader = writer.reader().unwrap();
+		writer.write(&9u32).unwrap();
+		assert!(writer.truncate(0).is_err());
+		writer.truncate(4).unwrap();
+		assert_eq!(reader.read::<u32>(pos).unwrap(), 7);
+	}
+}
 
+/// `Reader` reads values from a diary file through a memory map of the file, so reads
+/// within the map cost no system calls. Clones share the open file and the map. Bytes
+/// at or beyond `file_size` are unreadable. When the file is not mapped, as off Unix, or
+/// the map ends before `file_size`, reads fall back to positional reads of the file.
+#[derive(Debug, Clone)]
 pub struct Reader {
-	file_path: PathBuf,
-	pub file: File,
+	file: Arc<File>,
+	maps: Arc<Mutex<Option<Arc<Mmap>>>>,
+	map: Option<Arc<Mmap>>,
+	cache: Option<Arc<Cache>>,
 	pub file_size: usize,
 }
 
 impl Reader {
 	pub fn read_say(&mut self, pos: diary::SayPos) -> io::Result<Say> {
-		let sayer = self.read::<Sayer>(pos.sayer)?;
-		let object = self.read::<ObjectId>(pos.object)?;
-		let point = self.read::<Point>(pos.point)?;
-		let target = self.read::<Target>(pos.target)?;
-		let say = Say { sayer, object, point, target: Some(target) };
-		Ok(say)
+		self.read::<Say>(pos.sayer)
+	}
+
+	pub fn read<V: ReadBytes<V>>(&self, pos: diary::Pos) -> io::Result<V> {
+		let start = usize::from(pos);
+		match &self.map {
+			Some(map) if map.len() >= self.file_size => {
+				let mut bytes = &map[min(start, self.file_size)..self.file_size];
+				V::read_bytes(&mut bytes)
+			}
+			_ => {
+				let mut bytes = PositionalBytes { file: &self.file, pos: start as u64, end: self.file_size as u64 };
+				V::read_bytes(&mut bytes)
+			}
+		}
+	}
+
+	/// Reads the bytes from a position up to a length in one read. Borrows the bytes from
+	/// the map when it covers them.
+	pub fn read_exact(&self, pos: diary::Pos, len: usize) -> io::Result<Cow<'_, [u8]>> {
+		let start = usize::from(pos);
+		let end = start.checked_add(len).filter(|end| *end <= self.file_size)
+			.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+		match &self.map {
+			Some(map) if map.len() >= self.file_size => Ok(Cow::Borrowed(&map[start..end])),
+			_ => {
+				let mut bytes = vec![0u8; len];
+				let mut reader = PositionalBytes { file: &self.file, pos: start as u64, end: end as u64 };
+				reader.read_exact(&mut bytes)?;
+				Ok(Cow::Owned(bytes))
+			}
+		}
+	}
+
+	/// Reads the value at a position through the reader's cache, if it has one.
+	pub fn read_cached<V: ReadBytes<V> + Clone + Send + Sync + 'static>(&self, pos: diary::Pos) -> io::Result<V> {
+		match &self.cache {
+			Some(cache) => cache.value(pos.into(), || self.read(pos)),
+			None => self.read(pos),
+		}
 	}
 
-	pub fn read<V: ReadBytes<V>>(&mut self, pos: diary::Pos) -> io::Result<V> {
-		self.file.seek(SeekFrom::Start(pos.into()))?;
-		V::read_bytes(&mut self.file)
+	pub fn cache(&self) -> Option<&Arc<Cache>> { self.cache.as_ref() }
+
+	/// Constructs a reader sharing this reader's file and using the cache.
+	pub fn with_cache(&self, cache: Arc<Cache>) -> Reader {
+		Reader { cache: Some(cache), ..self.clone() }
+	}
+
+	/// Constructs a reader sharing this reader's file and cache with a different length.
+	/// The file is remapped for all readers sharing it when it has grown beyond the map.
+	pub fn with_size(&self, file_size: usize) -> Reader {
+		let map = map_of(&self.file, &self.maps, file_size);
+		Reader { map, file_size, ..self.clone() }
 	}
 
 	pub fn new(file_path: &Path, file_size: usize) -> io::Result<Reader> {
-		let file = OpenOptions::new().read(true).open(file_path)?;
-		Ok(Reader { file, file_size, file_path: file_path.to_path_buf() })
+		let file = Arc::new(OpenOptions::new().read(true).open(file_path)?);
+		let maps = Arc::new(Mutex::new(None));
+		let map = map_of(&file, &maps, file_size);
+		Ok(Reader { file, maps, map, cache: None, file_size })
+	}
+}
+
+/// Finds a map of the file covering at least the given length, remapping the file when
+/// the shared map is shorter. Returns `None` when the file cannot be mapped.
+#[cfg(unix)]
+fn map_of(file: &File, maps: &Mutex<Option<Arc<Mmap>>>, len: usize) -> Option<Arc<Mmap>> {
+	let mut maps = maps.lock().unwrap_or_else(PoisonError::into_inner);
+	match maps.as_ref() {
+		Some(map) if map.len() >= len => Some(map.clone()),
+		_ => {
+			// SAFETY: Readers read only below their `file_size`, which is at most the diary
+			// end of a commit. Bytes below a commit's diary end are never rewritten. The
+			// writer truncates the diary to drop failed writes, but `Writer::seal` stops it
+			// below the end it last published, and recovery only truncates past the last
+			// good commit. The map may extend past the truncation point, but no reader
+			// touches those pages.
+			let map = unsafe { Mmap::map(file) }.ok().map(Arc::new);
+			if map.is_some() {
+				*maps = map.clone();
+			}
+			map
+		}
 	}
 }
 
-impl Clone for Reader {
-	fn clone(&self) -> Self { Reader::new(&self.file_path, self.file_size).unwrap() }
-}
\ No newline at end of file
+/// Leaves the file unmapped. Windows refuses to shrink a mapped file, and the writer
+/// shrinks the diary to drop failed writes, so readers there use positional reads.
+#[cfg(not(unix))]
+fn map_of(_file: &File, _maps: &Mutex<Option<Arc<Mmap>>>, _len: usize) -> Option<Arc<Mmap>> {
+	None
+}
+
+/// Reads the bytes of a file from a position up to an end without moving the file's cursor.
+struct PositionalBytes<'a> {
+	file: &'a File,
+	pos: u64,
+	end: u64,
+}
+
+impl<'a> Read for PositionalBytes<'a> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let len = min(buf.len() as u64, self.end.saturating_sub(self.pos)) as usize;
+		let read_len = read_at(self.file, &mut buf[..len], self.pos)?;
+		self.pos += read_len as u64;
+		Ok(read_len)
+	}
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], pos: u64) -> io::Result<usize> {
+	std::os::unix::fs::FileExt::read_at(file, buf, pos)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], pos: u64) -> io::Result<usize> {
+	std::os::windows::fs::FileExt::seek_read(file, buf, pos)
+}
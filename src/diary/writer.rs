@@ -1,16 +1,17 @@
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::{Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::io::{ErrorKind, Seek, SeekFrom};
+use std::path::Path;
 
 use crate::{diary, Say};
 use crate::bytes::WriteBytes;
 use crate::diary::{Pos, SayPos};
 
 pub struct Writer {
-	path: PathBuf,
 	file: File,
+	reader: diary::Reader,
 	end_size: usize,
+	sealed_size: usize,
 }
 
 impl Writer {
@@ -31,41 +32,49 @@ impl Writer {
 		}
 	}
 
+	/// Writes the say as one value and returns the positions of its parts.
 	pub fn write_say(&mut self, say: &Say) -> io::Result<SayPos> {
-		let start = self.end_size;
-		match self.try_write(say) {
-			Ok(pos) => Ok(pos),
-			Err(e) => {
-				self.file.set_len(start as u64)?;
-				Err(e)
-			}
-		}
-	}
-
-	fn try_write(&mut self, say: &Say) -> io::Result<SayPos> {
-		let start = self.end_size;
-		let (sayer_start, sayer_size) = self.write(&say.sayer)?;
-		let (object_start, object_size) = self.write(&say.object)?;
-		let (point_start, point_size) = self.write(&say.point)?;
-		let target = match &say.target {
-			None => unimplemented!(),
-			Some(it) => it.clone(),
-		};
-		let (target_start, target_size) = self.write(&target)?;
-		let end = Pos::at(start + sayer_size + object_size + point_size + target_size);
-		let say_pos = SayPos { sayer: sayer_start, object: object_start, point: point_start, target: target_start, end };
-		Ok(say_pos)
+		let (start, size) = self.write(say)?;
+		let object = start + Pos::at(byte_len(&say.sayer)?);
+		let point = object + Pos::at(byte_len(&say.object)?);
+		let target = point + Pos::at(byte_len(&say.point)?);
+		let end = start + Pos::at(size);
+		Ok(SayPos { sayer: start, object, point, target, end })
 	}
 
 	pub fn reader(&self) -> io::Result<diary::Reader> {
-		diary::Reader::new(&self.path, self.end_size)
+		Ok(self.reader.with_size(self.end_size))
 	}
 
 	pub fn end_size(&self) -> usize { self.end_size }
 
+	/// Protects the bytes below the size from `truncate`. Readers given that size may
+	/// have mapped them, and a mapped page cut from the file faults when read.
+	pub fn seal(&mut self, size: usize) {
+		self.sealed_size = self.sealed_size.max(size);
+	}
+
+	/// Drops the bytes written after the given end. Refuses to drop sealed bytes.
+	pub fn truncate(&mut self, end_size: usize) -> io::Result<()> {
+		if end_size < self.sealed_size {
+			return Err(io::Error::new(ErrorKind::InvalidInput, format!("Diary bytes below {} are sealed", self.sealed_size)));
+		}
+		self.file.set_len(end_size as u64)?;
+		self.end_size = end_size;
+		Ok(())
+	}
+
+	/// Flushes the written bytes to stable storage.
+	pub fn sync(&self) -> io::Result<()> { self.file.sync_data() }
+
 	pub fn new(path: &Path, file_len: usize) -> io::Result<Writer> {
 		let file = OpenOptions::new().append(true).create(true).open(path)?;
 		file.set_len(file_len as u64)?;
-		Ok(Writer { path: path.to_owned(), file, end_size: file_len })
+		let reader = diary::Reader::new(path, file_len)?;
+		Ok(Writer { file, reader, end_size: file_len, sealed_size: file_len })
 	}
 }
+
+fn byte_len(value: &impl WriteBytes) -> io::Result<usize> {
+	value.write_bytes(&mut io::sink())
+}
@@ -1,76 +1,487 @@
 use std::{io, thread};
-use std::fs::{File, OpenOptions};
-use std::io::{Seek, SeekFrom};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::ops::{Bound, RangeBounds, RangeInclusive};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, LazyLock, Mutex, MutexGuard, PoisonError, Weak};
 use std::sync::mpsc::{channel, Sender, sync_channel, SyncSender};
+use std::thread::JoinHandle;
 
+pub use options::{CacheOptions, Durability, EchoOptions, Locking};
+pub use read_only::ReadOnlyEcho;
+pub use recovery::Recovery;
+pub use subscription::{Change, ChangeFilter, Subscription};
+pub use transaction::Transaction;
+pub use transaction_log::{TransactionRecord, Transactions};
+pub use write_error::WriteError;
 pub use write_scope::WriteScope;
 
-use crate::{Chamber, diary, hamt, Say, Speech};
-use crate::bytes::{ReadBytes, WriteBytes};
-use crate::diary::Diary;
-use crate::hamt::{Hamt, ProdAB, Root, ROOT_LEN};
-use crate::util::io_error;
+use crate::{Chamber, diary, EchoError, ObjectId, Say, Sayer, Speech};
+use crate::diary::{Cache, CacheStats, Diary};
+use crate::error::duplicate_io_error;
+pub(crate) use crate::echo::roots_log::RootsReader;
+use crate::echo::roots_log::{Commit, RootsLog};
+use crate::echo::subscription::Subscriber;
+use crate::echo::published::{Published, Snapshot};
+use crate::echo::transaction::Observation;
+use crate::hamt;
+use crate::hamt::{Hamt, NestedTransient, Root};
+use crate::util::writer_gone;
 
+#[cfg(test)]
+mod tests {
+	use std::error::Error;
+	use std::io;
+	use std::sync::mpsc::channel;
+
+	use crate::{Chamber, ObjectId, Point, Say, Sayer, Speech, Target};
+	use crate::echo::{Change, ChangeFilter, EchoOptions, InnerEcho, Locking};
+	use crate::echo::subscription::subscription;
+	use crate::util::temp_dir;
+
+	#[test]
+	fn coordinated_writers_build_on_each_other() -> Result<(), Box<dyn Error>> {
+		let object = ObjectId::new("Dracula");
+		let count = Point::Static { name: "count", aspect: "Counter" };
+		let max_count = Point::Static { name: "max_count", aspect: "Counter" };
+		let folder = temp_dir("echo")?;
+		let options = EchoOptions::default().locking(Locking::Coordinated);
+		let mut first = InnerEcho::new(folder.clone(), options)?;
+		let mut second = InnerEcho::new(folder, options)?;
+		write(&mut first, number_speech(&object, &count, 1))?;
+		write(&mut second, number_speech(&object, &max_count, 10))?;
+		let chamber = write(&mut first, number_speech(&object, &count, 2))?;
+		assert_eq!(chamber.version(), 3);
+		assert_eq!((chamber.number(&object, &count), chamber.number(&object, &max_count)), (2, 10));
+		second.follow()?;
+		assert_eq!(second.chamber()?.number(&object, &count), 2);
+		Ok(())
+	}
+
+	#[test]
+	fn dropped_subscriptions_are_forgotten_whatever_their_filter() -> Result<(), Box<dyn Error>> {
+		let object = ObjectId::new("Dracula");
+		let count = Point::Static { name: "count", aspect: "Counter" };
+		let mut echo = InnerEcho::new(temp_dir("echo")?, EchoOptions::default())?;
+		let (kept, kept_subscription) = subscription::<Change>();
+		let (dropped, dropped_subscription) = subscription::<Change>();
+		echo.subscribers.push((ChangeFilter::Object(ObjectId::Unit), kept));
+		echo.subscribers.push((ChangeFilter::Object(ObjectId::Unit), dropped));
+		drop(dropped_subscription);
+		write(&mut echo, number_speech(&object, &count, 1))?;
+		assert_eq!(echo.subscribers.len(), 1);
+		assert!(kept_subscription.try_recv().is_err());
+		Ok(())
+	}
+
+	fn write(echo: &mut InnerEcho, speech: Speech) -> io::Result<Chamber> {
+		let (tx, rx) = channel();
+		echo.write_speeches(vec![(speech, Vec::new(), tx)]);
+		rx.recv().unwrap()
+	}
+
+	fn number_speech(object: &ObjectId, point: &Point, n: u64) -> Speech {
+		Speech {
+			sayer: Sayer::Unit,
+			says: vec![Say { sayer: Sayer::Unit, object: object.clone(), point: point.clone(), target: Some(Target::Number(n)) }],
+			retracted_objects: Vec::new(),
+			metadata: Vec::new(),
+			bulk: false,
+		}
+	}
+
+	#[test]
+	fn rollback_leaves_next_speech_unaffected() -> Result<(), Box<dyn Error>> {
+		let object = ObjectId::new("Dracula");
+		let point = Point::Static { name: "count", aspect: "Counter" };
+		let speech = |n: u64| number_speech(&object, &point, n);
+		let mut echo = InnerEcho::new(temp_dir("echo")?, EchoOptions::default())?;
+		let checkpoint = echo.checkpoint();
+		echo.write_speech(speech(1))?;
+		// Abandon the speech as a failure in its flush would.
+		echo.roots_log.discard_staged();
+		echo.rollback(checkpoint)?;
+		assert_eq!(echo.diary_writer.end_size(), checkpoint.diary_end);
+		echo.write_speech(speech(2))?;
+		echo.flush()?;
+		let chamber = echo.chamber()?;
+		assert_eq!(chamber.version(), 1);
+		assert_eq!(chamber.read_target(&object, &point)?, Some(Target::Number(2)));
+		Ok(())
+	}
+}
+
+mod options;
+mod published;
+mod read_only;
+mod recovery;
+mod roots_log;
+mod subscription;
+mod transaction;
+mod transaction_log;
+mod write_error;
 mod write_scope;
 
 #[derive(Debug, Clone)]
 pub struct Echo {
 	tx: SyncSender<Action>,
+	writer: Arc<WriterThread>,
+}
+
+/// `WriterThread` owns the writer thread of an Echo and closes it when the last
+/// clone of the Echo drops.
+#[derive(Debug)]
+struct WriterThread {
+	tx: SyncSender<Action>,
+	handle: Mutex<Option<JoinHandle<io::Result<()>>>>,
+	published: Arc<Published>,
+	options: EchoOptions,
+	folder: Arc<Folder>,
+}
+
+impl WriterThread {
+	fn is_running(&self) -> bool {
+		self.handle.lock().unwrap_or_else(PoisonError::into_inner).is_some()
+	}
+
+	/// Asks the thread to finish the actions queued ahead of the request, sync the
+	/// files and stop, then waits for it.
+	/// The folder stays open to other connects until the thread has stopped and
+	/// released the folder's lock.
+	fn close(&self) -> io::Result<()> {
+		let handle = {
+			let mut state = self.folder.lock();
+			let handle = self.handle.lock().unwrap_or_else(PoisonError::into_inner).take();
+			state.running &= handle.is_none();
+			handle
+		};
+		match handle {
+			None => Ok(()),
+			Some(handle) => {
+				self.tx.send(Action::Close).ok();
+				let result = handle.join().map_err(|_| io::Error::other("Echo writer panicked"));
+				self.folder.lock().open = false;
+				self.folder.changed.notify_all();
+				result?
+			}
+		}
+	}
+}
+
+impl Drop for WriterThread {
+	fn drop(&mut self) {
+		// Nothing is left to report the error to.
+		self.close().ok();
+	}
 }
 
 enum Action {
-	Speech(Speech, Sender<io::Result<Chamber>>),
-	Latest(Sender<Chamber>),
+	Speech(Speech, Vec<Observation>, Sender<io::Result<Chamber>>),
+	Latest(Sender<io::Result<Chamber>>),
+	Past(u64, Sender<io::Result<Chamber>>),
+	Versions(Sender<io::Result<RangeInclusive<u64>>>),
+	Subscribe(ChangeFilter, Subscriber<Change>),
+	Recovery(Sender<Recovery>),
+	Close,
 }
 
 impl Echo {
 	/// Connects to an Echo.
-	pub fn connect(name: &str, folder: &Path) -> Self {
+	pub fn connect(name: &str, folder: &Path) -> Result<Self, EchoError> {
+		Echo::connect_with(name, folder, EchoOptions::default())
+	}
+
+	/// Connects to an Echo configured by the given options. When the Echo is already
+	/// open in this process, returns a clone of it, or fails with
+	/// `EchoError::OptionsMismatch` when it was opened with other options.
+	pub fn connect_with(name: &str, folder: &Path, options: EchoOptions) -> Result<Self, EchoError> {
+		let mut folder_path = folder.to_path_buf();
+		folder_path.push(name);
+		std::fs::create_dir_all(&folder_path)?;
+		let folder_path = folder_path.canonicalize()?;
+		let folder = {
+			let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+			registry.retain(|_, folder| Arc::strong_count(folder) > 1);
+			registry.entry(folder_path.clone()).or_default().clone()
+		};
+		let mut state = folder.lock();
+		loop {
+			state = folder.changed.wait_while(state, |state| !state.settled()).unwrap_or_else(PoisonError::into_inner);
+			if !state.open {
+				break;
+			}
+			// The writer is upgraded under the lock only to be dropped outside it, since
+			// dropping the last clone closes the writer, which takes the lock.
+			if let Some(writer) = state.writer.upgrade() {
+				drop(state);
+				if writer.options != options {
+					return Err(EchoError::OptionsMismatch(writer.options));
+				}
+				return Ok(Echo { tx: writer.tx.clone(), writer });
+			}
+		}
+		state.opening = true;
+		drop(state);
+		let echo = Echo::spawn(folder_path, options, folder.clone());
+		let mut state = folder.lock();
+		state.opening = false;
+		if let Ok(echo) = &echo {
+			state.writer = Arc::downgrade(&echo.writer);
+			state.open = true;
+			state.running = true;
+		}
+		folder.changed.notify_all();
+		Ok(echo?)
+	}
+
+	/// Opens an Echo written by another process for reading only.
+	pub fn open_read_only(name: &str, folder: &Path) -> Result<ReadOnlyEcho, EchoError> {
 		let mut folder_path = folder.to_path_buf();
 		folder_path.push(name);
-		std::fs::create_dir_all(&folder_path).unwrap();
+		Ok(ReadOnlyEcho::open(folder_path)?)
+	}
+
+	fn spawn(folder_path: PathBuf, options: EchoOptions, folder: Arc<Folder>) -> io::Result<Self> {
+		let mut echo = InnerEcho::new(folder_path, options)?;
+		let published = echo.published.clone();
 		let (tx, rx) = sync_channel::<Action>(64);
-		thread::spawn(move || {
-			let mut echo = InnerEcho::new(folder_path);
-			for action in rx {
+		// Sends to a caller that has hung up are ignored.
+		let handle = thread::spawn(move || {
+			let mut next_action = None;
+			while let Some(action) = next_action.take().or_else(|| rx.recv().ok()) {
 				match action {
-					Action::Speech(speech, tx) => {
-						let new_chamber = echo.write_speech(speech);
-						tx.send(new_chamber).unwrap();
+					Action::Speech(speech, observations, tx) => {
+						let mut speeches = vec![(speech, observations, tx)];
+						if options.durability == Durability::GroupCommit {
+							while let Ok(action) = rx.try_recv() {
+								match action {
+									Action::Speech(speech, observations, tx) => speeches.push((speech, observations, tx)),
+									action => {
+										next_action = Some(action);
+										break;
+									}
+								}
+							}
+						}
+						echo.write_speeches(speeches);
 					}
 					Action::Latest(tx) => {
-						let chamber = echo.chamber().unwrap();
-						tx.send(chamber).unwrap();
+						let chamber = echo.follow().and_then(|()| echo.chamber());
+						tx.send(chamber).ok();
+					}
+					Action::Past(version, tx) => {
+						let chamber = echo.chamber_at(version);
+						tx.send(chamber).ok();
+					}
+					Action::Versions(tx) => {
+						let versions = echo.follow().map(|()| 1..=echo.roots_log.version);
+						tx.send(versions).ok();
+					}
+					Action::Subscribe(filter, subscriber) => {
+						echo.subscribers.push((filter, subscriber));
 					}
+					Action::Recovery(tx) => {
+						tx.send(echo.recovery).ok();
+					}
+					Action::Close => break,
 				}
 			}
+			echo.sync()
 		});
-		Echo { tx }
+		let writer = WriterThread { tx: tx.clone(), handle: Mutex::new(Some(handle)), published, options, folder };
+		Ok(Echo { tx, writer: Arc::new(writer) })
+	}
+
+	/// Finishes the actions already queued, syncs the files and stops the writer
+	/// thread. Reports the error from the final sync. Later operations on this Echo
+	/// and its clones fail with `EchoError::WriterGone`. The Echo closes the same way
+	/// when its last clone drops.
+	pub fn close(&self) -> Result<(), EchoError> {
+		Ok(self.writer.close()?)
 	}
 
 	/// Opens a scope for writing facts to the database and provides it to the
 	/// given function.
-	pub fn write<R>(&self, f: impl Fn(&mut WriteScope) -> R) -> io::Result<R> {
-		let mut write = WriteScope { says: Vec::new() };
+	pub fn write<R>(&self, f: impl Fn(&mut WriteScope) -> R) -> Result<R, EchoError> {
+		self.write_as(&Sayer::Unit, f)
+	}
+
+	/// Opens a scope for writing facts to the database attributed to the given
+	/// sayer and provides it to the given function.
+	pub fn write_as<R>(&self, sayer: &Sayer, f: impl Fn(&mut WriteScope) -> R) -> Result<R, EchoError> {
+		let mut write = WriteScope::new(sayer);
 		let result = f(&mut write);
-		self.write_speech(Speech { says: write.says })?;
+		self.write_speech(write.into_speech(), Vec::new())?;
+		Ok(result)
+	}
+
+	/// Opens a scope for writing facts to the database and provides it to the
+	/// given function. The facts are written only when the function returns `Ok`.
+	pub fn try_write<R, E>(&self, f: impl FnOnce(&mut WriteScope) -> Result<R, E>) -> Result<R, WriteError<E>> {
+		self.try_write_as(&Sayer::Unit, f)
+	}
+
+	/// Opens a scope for writing facts to the database attributed to the given
+	/// sayer and provides it to the given function. The facts are written only when
+	/// the function returns `Ok`.
+	pub fn try_write_as<R, E>(&self, sayer: &Sayer, f: impl FnOnce(&mut WriteScope) -> Result<R, E>) -> Result<R, WriteError<E>> {
+		let mut write = WriteScope::new(sayer);
+		let result = f(&mut write).map_err(WriteError::Aborted)?;
+		self.write_speech(write.into_speech(), Vec::new())?;
 		Ok(result)
 	}
 
-	fn write_speech(&self, speech: Speech) -> io::Result<Chamber> {
+	/// Writes the says in one commit by building the changed parts of the database
+	/// bottom-up, which suits importing many facts. Where several says share a point
+	/// on an object, the last one counts, as with `write`. Retractions are applied
+	/// after the other says are loaded.
+	pub fn load(&self, says: impl IntoIterator<Item=Say>) -> Result<(), EchoError> {
+		let speech = Speech { sayer: Sayer::Unit, says: says.into_iter().collect(), retracted_objects: Vec::new(), metadata: Vec::new(), bulk: true };
+		self.write_speech(speech, Vec::new())?;
+		Ok(())
+	}
+
+	/// Opens a transaction that reads the latest facts and its own pending says, and
+	/// provides it to the given function. When a concurrent commit changes facts the
+	/// transaction read, the function is run again against the newer facts. After
+	/// `TRANSACT_ATTEMPTS` conflicting runs the error is `EchoError::Conflict`.
+	pub fn transact<R>(&self, f: impl Fn(&mut Transaction) -> Result<R, EchoError>) -> Result<R, EchoError> {
+		self.transact_as(&Sayer::Unit, f)
+	}
+
+	/// Runs a transaction like `transact` with its says attributed to the given sayer.
+	pub fn transact_as<R>(&self, sayer: &Sayer, f: impl Fn(&mut Transaction) -> Result<R, EchoError>) -> Result<R, EchoError> {
+		for _ in 0..TRANSACT_ATTEMPTS {
+			let mut transaction = Transaction::new(self.chamber()?, sayer);
+			let result = f(&mut transaction)?;
+			let (speech, observations) = transaction.into_parts();
+			match self.write_speech(speech, observations).map_err(EchoError::from) {
+				Ok(_) => return Ok(result),
+				Err(EchoError::Conflict) => continue,
+				Err(e) => return Err(e),
+			}
+		}
+		Err(EchoError::Conflict)
+	}
+
+	fn write_speech(&self, speech: Speech, observations: Vec<Observation>) -> io::Result<Chamber> {
 		let (tx, rx) = channel::<io::Result<Chamber>>();
-		let action = Action::Speech(speech, tx);
-		self.tx.send(action).unwrap();
-		rx.recv().map_err(io_error)?
+		let action = Action::Speech(speech, observations, tx);
+		self.tx.send(action).map_err(writer_gone)?;
+		rx.recv().map_err(writer_gone)?
 	}
 
 	/// Constructs a chamber for reading facts from the database.
-	pub fn chamber(&self) -> io::Result<Chamber> {
-		let (tx, rx) = channel::<Chamber>();
+	/// An exclusive writer's chamber is built from the commit the writer last flushed.
+	/// A coordinated writer is first asked to catch up with other writers.
+	pub fn chamber(&self) -> Result<Chamber, EchoError> {
+		if self.writer.options.locking == Locking::Exclusive && self.writer.is_running() {
+			return Ok(self.writer.published.chamber()?);
+		}
+		let (tx, rx) = channel::<io::Result<Chamber>>();
 		let action = Action::Latest(tx);
-		self.tx.send(action).unwrap();
-		rx.recv().map_err(io_error)
+		self.tx.send(action).map_err(writer_gone)?;
+		Ok(rx.recv().map_err(writer_gone)??)
+	}
+
+	/// Lists the versions of the database, one for each commit, from oldest to newest.
+	pub fn versions(&self) -> Result<RangeInclusive<u64>, EchoError> {
+		let (tx, rx) = channel::<io::Result<RangeInclusive<u64>>>();
+		let action = Action::Versions(tx);
+		self.tx.send(action).map_err(writer_gone)?;
+		Ok(rx.recv().map_err(writer_gone)??)
+	}
+
+	/// Constructs a chamber for reading facts as they stood after the commit
+	/// with the given version.
+	pub fn chamber_at(&self, version: u64) -> Result<Chamber, EchoError> {
+		let (tx, rx) = channel::<io::Result<Chamber>>();
+		let action = Action::Past(version, tx);
+		self.tx.send(action).map_err(writer_gone)?;
+		Ok(rx.recv().map_err(writer_gone)??)
+	}
+
+	/// Reports the hit and miss counts of the cache shared by the Echo's chambers.
+	pub fn cache_stats(&self) -> Option<CacheStats> {
+		self.writer.published.cache_stats()
+	}
+
+	/// Reports the repairs made to the Echo's files when it was opened.
+	pub fn recovery(&self) -> Result<Recovery, EchoError> {
+		let (tx, rx) = channel::<Recovery>();
+		let action = Action::Recovery(tx);
+		self.tx.send(action).map_err(writer_gone)?;
+		Ok(rx.recv().map_err(writer_gone)?)
+	}
+
+	/// Subscribes to every later commit.
+	pub fn subscribe(&self) -> Subscription<Change> {
+		self.subscribe_to(ChangeFilter::All)
+	}
+
+	/// Subscribes to later commits with says selected by the filter. Each change
+	/// holds only the selected says. When the writer has stopped the receiver is
+	/// already disconnected.
+	pub fn subscribe_to(&self, filter: ChangeFilter) -> Subscription<Change> {
+		let (subscriber, subscription) = subscription::subscription();
+		let action = Action::Subscribe(filter, subscriber);
+		self.tx.send(action).ok();
+		subscription
+	}
+
+	/// Reads the transaction records of the commits with the given versions, oldest first.
+	pub fn transactions(&self, versions: impl RangeBounds<u64>) -> Result<Transactions, EchoError> {
+		let chamber = self.chamber()?;
+		let first = match versions.start_bound() {
+			Bound::Included(n) => *n,
+			Bound::Excluded(n) => n + 1,
+			Bound::Unbounded => 1,
+		}.max(1);
+		let last = match versions.end_bound() {
+			Bound::Included(n) => *n,
+			Bound::Excluded(n) => n.saturating_sub(1),
+			Bound::Unbounded => chamber.version,
+		}.min(chamber.version);
+		let commits = chamber.roots_reader.read_commits(first..=last)?;
+		Ok(Transactions::new(commits, chamber.diary_reader))
+	}
+}
+
+const TRANSACT_ATTEMPTS: usize = 16;
+
+/// The folders of the Echos open in this process, by canonical folder path. The registry
+/// is locked only to find a folder; opening and closing wait on the folder alone.
+static REGISTRY: LazyLock<Mutex<HashMap<PathBuf, Arc<Folder>>>> = LazyLock::new(Default::default);
+
+/// `Folder` tracks the writer of one folder in this process.
+#[derive(Debug, Default)]
+struct Folder {
+	state: Mutex<FolderState>,
+	changed: Condvar,
+}
+
+/// `FolderState` holds the folder's writer. `open` stays set from the writer's spawn until
+/// its thread has stopped, and `running` until the writer is asked to close.
+#[derive(Debug, Default)]
+struct FolderState {
+	writer: Weak<WriterThread>,
+	opening: bool,
+	open: bool,
+	running: bool,
+}
+
+impl Folder {
+	fn lock(&self) -> MutexGuard<'_, FolderState> {
+		self.state.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+impl FolderState {
+	/// Checks the folder is neither opening nor closing, so it has a running writer with
+	/// a live clone, or no writer at all. A writer whose last clone has dropped is still
+	/// closing.
+	fn settled(&self) -> bool {
+		!self.opening && (!self.open || (self.running && self.writer.strong_count() > 0))
 	}
 }
 
@@ -80,47 +491,284 @@ struct InnerEcho {
 	object_points: Hamt,
 	point_objects: Hamt,
 	roots_log: RootsLog,
+	subscribers: Vec<(ChangeFilter, Subscriber<Change>)>,
+	durability: Durability,
+	recovery: Recovery,
+	folder_path: PathBuf,
+	lock_file: File,
+	locking: Locking,
+	published: Arc<Published>,
+}
+
+type SpeechRequest = (Speech, Vec<Observation>, Sender<io::Result<Chamber>>);
+
+/// The roots and diary end of the writer before a change, restored when the change fails.
+#[derive(Debug, Copy, Clone)]
+struct Checkpoint {
+	roots: (Root, Root),
+	diary_end: usize,
 }
 
 impl InnerEcho {
-	fn write_speech(&mut self, speech: Speech) -> io::Result<Chamber> {
-		for say in speech.says.into_iter() {
-			let mut diary_reader = self.diary_writer.reader()?;
-			self.write_object_points(&say, &mut diary_reader)?;
-			self.write_point_objects(&say, &mut diary_reader)?;
+	/// Applies the speeches, flushes them together, and then answers each writer with
+	/// a chamber at its commit.
+	fn write_speeches(&mut self, speeches: Vec<SpeechRequest>) {
+		let coordinated = self.locking == Locking::Coordinated;
+		if coordinated {
+			if let Err(e) = self.lock_latest() {
+				for (_, _, tx) in speeches {
+					tx.send(Err(duplicate_io_error(&e))).ok();
+				}
+				return;
+			}
+		}
+		let checkpoint = self.checkpoint();
+		let written = speeches.into_iter()
+			.map(|(speech, observations, tx)| (self.write_observed_speech(speech, &observations), tx))
+			.collect::<Vec<_>>();
+		let flushed = self.flush().or_else(|e| self.rollback(checkpoint).and(Err(e)));
+		if flushed.is_ok() {
+			self.publish();
+		}
+		if coordinated {
+			// Closing the lock file at shutdown releases a lock that fails to unlock here.
+			self.lock_file.unlock().ok();
+		}
+		for (record, tx) in written {
+			let answer = match &flushed {
+				Ok(()) => record.and_then(|record| {
+					self.notify_subscribers(&record);
+					self.chamber_at(record.version)
+				}),
+				Err(e) => Err(duplicate_io_error(e)),
+			};
+			tx.send(answer).ok();
 		}
-		self.diary.commit(self.diary_writer.end_size());
-		self.roots_log.write_roots(self.object_points.root, self.point_objects.root)?;
-		self.chamber()
 	}
 
-	fn write_point_objects(&mut self, say: &Say, diary_reader: &mut diary::Reader) -> io::Result<()> {
-		let object_targets_root = match self.point_objects.reader()?.read_value(&say.point, diary_reader)? {
-			None => Root::ZERO,
-			Some(root) => root
+	/// Writes the staged commits to the roots log after the diary bytes they point to,
+	/// syncing both files unless durability is `None`. On failure the staged commits
+	/// are abandoned.
+	fn flush(&mut self) -> io::Result<()> {
+		let sync = self.durability != Durability::None;
+		let result = match sync {
+			true => self.diary_writer.sync(),
+			false => Ok(()),
 		};
-		let mut object_targets = Hamt::new(object_targets_root);
-		let target = match &say.target {
-			None => unimplemented!(),
-			Some(it) => it.clone(),
-		};
-		let object_target = ProdAB { a: say.object.to_owned(), b: target };
-		object_targets.write_value(&say.object, &object_target, &mut self.diary_writer)?;
-		self.point_objects.write_value(&say.point, &object_targets.root, &mut self.diary_writer)
+		match result {
+			Ok(()) => self.roots_log.flush(sync),
+			Err(e) => {
+				self.roots_log.discard_staged();
+				Err(e)
+			}
+		}
+	}
+
+	/// Takes the folder's lock and catches up with the commits of other writers, first
+	/// repairing any commit left torn by a writer that crashed.
+	fn lock_latest(&mut self) -> io::Result<()> {
+		self.lock_file.lock()?;
+		let result = recovery::recover(&file_path("roots.dat", &self.folder_path), &self.diary.file_path)
+			.and_then(|_| self.reload())
+			.and_then(|diary_end| self.diary_writer.truncate(diary_end));
+		if result.is_err() {
+			self.lock_file.unlock()?;
+		}
+		result
+	}
+
+	/// In coordinated mode, catches up with the commits of other writers.
+	fn follow(&mut self) -> io::Result<()> {
+		if self.locking != Locking::Coordinated {
+			return Ok(());
+		}
+		self.lock_file.lock_shared()?;
+		let result = self.reload();
+		self.lock_file.unlock()?;
+		result.map(|_| ())
+	}
+
+	/// Reads the latest roots and diary length from the files. Returns the diary length.
+	fn reload(&mut self) -> io::Result<usize> {
+		let diary_len = std::fs::metadata(&self.diary.file_path)?.len();
+		let diary_end = self.roots_log.reload(diary_len)?.map_or(0, |commit| commit.diary_end) as usize;
+		self.object_points = Hamt::new(self.roots_log.roots.0);
+		self.point_objects = Hamt::new(self.roots_log.roots.1);
+		self.diary.commit(diary_end);
+		self.publish();
+		Ok(diary_end)
+	}
+
+	/// Publishes the latest commit and seals the diary bytes its chambers may read.
+	fn publish(&mut self) {
+		let snapshot = self.snapshot();
+		self.diary_writer.seal(snapshot.diary_end);
+		self.published.publish(snapshot);
+	}
+
+	/// Describes the latest commit. Holds only flushed commits between speeches.
+	fn snapshot(&self) -> Snapshot {
+		Snapshot {
+			roots: (self.object_points.root, self.point_objects.root),
+			diary_end: self.diary.file_size.get(),
+			version: self.roots_log.version,
+		}
+	}
+
+	/// Flushes the diary and roots log to stable storage.
+	fn sync(&self) -> io::Result<()> {
+		self.diary_writer.sync()?;
+		self.roots_log.sync()
+	}
+
+	fn checkpoint(&self) -> Checkpoint {
+		Checkpoint {
+			roots: (self.object_points.root, self.point_objects.root),
+			diary_end: self.diary_writer.end_size(),
+		}
+	}
+
+	/// Returns the roots and the diary to the checkpoint, dropping diary bytes written since.
+	fn rollback(&mut self, checkpoint: Checkpoint) -> io::Result<()> {
+		self.object_points = Hamt::new(checkpoint.roots.0);
+		self.point_objects = Hamt::new(checkpoint.roots.1);
+		self.diary_writer.truncate(checkpoint.diary_end)?;
+		self.diary.commit(checkpoint.diary_end);
+		if let Some(cache) = self.diary.cache() {
+			cache.forget_from(checkpoint.diary_end);
+		}
+		Ok(())
+	}
+
+	fn write_observed_speech(&mut self, speech: Speech, observations: &[Observation]) -> io::Result<TransactionRecord> {
+		if !observations.is_empty() {
+			let chamber = self.chamber()?;
+			for observation in observations {
+				if !observation.holds(&chamber)? {
+					return Err(EchoError::Conflict.into());
+				}
+			}
+		}
+		self.write_speech(speech)
+	}
+
+	/// Writes the speech and stages its commit. On failure nothing of the speech remains.
+	fn write_speech(&mut self, speech: Speech) -> io::Result<TransactionRecord> {
+		let checkpoint = self.checkpoint();
+		self.try_write_speech(speech).or_else(|e| self.rollback(checkpoint).and(Err(e)))
 	}
 
-	fn write_object_points(&mut self, say: &Say, diary_reader: &mut diary::Reader) -> io::Result<()> {
-		let point_targets_root = match self.object_points.reader()?.read_value(&say.object, diary_reader)? {
-			None => Root::ZERO,
-			Some(it) => it,
+	fn try_write_speech(&mut self, speech: Speech) -> io::Result<TransactionRecord> {
+		let says = self.expand_speech(&speech.sayer, speech.retracted_objects, speech.says)?;
+		if speech.bulk {
+			self.load_says(&says)?;
+		} else {
+			self.write_says(&says)?;
+		}
+		let record = TransactionRecord {
+			version: self.roots_log.version + 1,
+			timestamp: TransactionRecord::now_millis(),
+			sayer: speech.sayer,
+			says,
+			metadata: speech.metadata,
 		};
-		let mut point_targets = Hamt::new(point_targets_root);
-		let target = match &say.target {
-			None => unimplemented!(),
-			Some(it) => it.clone(),
+		let (speech_pos, _size) = self.diary_writer.write(&record)?;
+		self.diary.commit(self.diary_writer.end_size());
+		self.roots_log.stage_commit(Commit {
+			roots: (self.object_points.root, self.point_objects.root),
+			speech: speech_pos,
+			diary_end: self.diary_writer.end_size() as u64,
+		});
+		Ok(record)
+	}
+
+	/// Writes says holding retractions through transient maps, so each changed frame is
+	/// written once.
+	fn write_says(&mut self, says: &[Say]) -> io::Result<()> {
+		let mut diary_reader = self.diary.reader()?;
+		let mut object_points = NestedTransient::new(self.object_points.root, &mut diary_reader)?;
+		let mut point_objects = NestedTransient::new(self.point_objects.root, &mut diary_reader)?;
+		for say in says {
+			// Both maps hold the position of a single copy of the say.
+			let say_pos = match say.target {
+				None => None,
+				Some(_) => Some(self.diary_writer.write(say)?.0),
+			};
+			object_points.write_value_pos(&say.object, &say.point, say_pos, &mut diary_reader)?;
+			point_objects.write_value_pos(&say.point, &say.object, say_pos, &mut diary_reader)?;
+		}
+		self.object_points = Hamt::new(object_points.commit(&mut self.diary_writer)?);
+		self.point_objects = Hamt::new(point_objects.commit(&mut self.diary_writer)?);
+		Ok(())
+	}
+
+	/// Writes says by building the changed parts of both maps bottom-up. Used by
+	/// `Echo::load`. Only the last say for a point on an object counts, so its
+	/// assertions are loaded first and its retractions written after them through
+	/// transient maps.
+	fn load_says(&mut self, says: &[Say]) -> io::Result<()> {
+		let mut last_says = HashMap::new();
+		for (index, say) in says.iter().enumerate() {
+			last_says.insert((&say.object, &say.point), index);
+		}
+		let mut object_entries = Vec::with_capacity(says.len());
+		let mut point_entries = Vec::with_capacity(says.len());
+		let mut retractions = Vec::new();
+		for (index, say) in says.iter().enumerate() {
+			if last_says[&(&say.object, &say.point)] != index {
+				continue;
+			}
+			match say.target {
+				None => retractions.push(say.to_owned()),
+				Some(_) => {
+					let (say_pos, _size) = self.diary_writer.write(say)?;
+					object_entries.push((say.object.clone(), say.point.clone(), say_pos));
+					point_entries.push((say.point.clone(), say.object.clone(), say_pos));
+				}
+			}
+		}
+		self.object_points = Hamt::new(hamt::load_nested(self.object_points.root, object_entries, &mut self.diary_writer)?);
+		self.point_objects = Hamt::new(hamt::load_nested(self.point_objects.root, point_entries, &mut self.diary_writer)?);
+		if !retractions.is_empty() {
+			// The transient maps read the loaded frames through the diary.
+			self.diary.commit(self.diary_writer.end_size());
+			self.write_says(&retractions)?;
+		}
+		Ok(())
+	}
+
+	/// Sends each subscriber the says it selects and forgets subscribers that have hung up.
+	/// The commit is already flushed, so a chamber that fails to open skips the
+	/// notification and keeps every live subscriber.
+	fn notify_subscribers(&mut self, record: &TransactionRecord) {
+		self.subscribers.retain(|(_, subscriber)| subscriber.is_alive());
+		if !self.subscribers.iter().any(|(filter, _)| filter.selects_any(&record.says)) {
+			return;
+		}
+		let chamber = match self.chamber_at(record.version) {
+			Ok(chamber) => chamber,
+			Err(_) => return,
 		};
-		point_targets.write_value(&say.point, &target, &mut self.diary_writer)?;
-		self.object_points.write_value(&say.object, &point_targets.root, &mut self.diary_writer)
+		self.subscribers.retain(|(filter, subscriber)| {
+			let says = filter.select(&record.says);
+			says.is_empty() || subscriber.send(Change { chamber: chamber.clone(), version: record.version, says })
+		});
+	}
+
+	/// Replaces each retracted object with a retraction of every point the object
+	/// currently holds, ahead of the other says.
+	fn expand_speech(&self, sayer: &Sayer, retracted_objects: Vec<ObjectId>, speech_says: Vec<Say>) -> io::Result<Vec<Say>> {
+		let mut says = Vec::new();
+		let mut diary_reader = self.diary_writer.reader()?;
+		for object in retracted_objects {
+			let point_targets_root: Option<Root> = self.object_points.reader()?.read_value(&object, &mut diary_reader)?;
+			if let Some(root) = point_targets_root {
+				let point_says = Hamt::new(root).reader()?.read_all::<Say>(&mut diary_reader)?;
+				says.extend(point_says.into_iter().map(|it| Say { sayer: sayer.to_owned(), target: None, ..it }));
+			}
+		}
+		says.extend(speech_says);
+		Ok(says)
 	}
 
 	fn chamber(&self) -> io::Result<Chamber> {
@@ -128,60 +776,64 @@ impl InnerEcho {
 			point_objects_reader: self.point_objects.reader()?,
 			object_points_reader: self.object_points.reader()?,
 			diary_reader: self.diary.reader()?,
+			roots_reader: self.roots_log.reader(),
+			version: self.roots_log.version,
 		};
 		Ok(chamber)
 	}
 
-	fn new(folder_path: PathBuf) -> Self {
-		let diary = Diary::load(&file_path("diary.dat", &folder_path)).unwrap();
-		let diary_writer = diary.writer().unwrap();
-		let roots_log = RootsLog::new(&folder_path).unwrap();
+	fn chamber_at(&mut self, version: u64) -> io::Result<Chamber> {
+		let (object_points_root, point_objects_root) = self.roots_log.read_commit(version)?.roots;
+		let chamber = Chamber {
+			point_objects_reader: Hamt::new(point_objects_root).reader()?,
+			object_points_reader: Hamt::new(object_points_root).reader()?,
+			diary_reader: self.diary.reader()?,
+			roots_reader: self.roots_log.reader(),
+			version,
+		};
+		Ok(chamber)
+	}
+
+	fn new(folder_path: PathBuf, options: EchoOptions) -> io::Result<Self> {
+		let lock_file = OpenOptions::new().write(true).create(true).truncate(false).open(file_path("echo.lock", &folder_path))?;
+		match options.locking {
+			Locking::Exclusive => lock_file.try_lock().map_err(|e| match e {
+				TryLockError::WouldBlock => io::Error::other(EchoError::Locked),
+				TryLockError::Error(e) => e,
+			})?,
+			Locking::Coordinated => lock_file.lock()?,
+		}
+		let diary_path = file_path("diary.dat", &folder_path);
+		let recovery = recovery::recover(&file_path("roots.dat", &folder_path), &diary_path)?;
+		let cache = Cache::new(options.cache.frames, options.cache.values);
+		let diary = Diary::load(&diary_path)?.with_cache(Arc::new(cache));
+		let diary_writer = diary.writer()?;
+		let roots_log = RootsLog::new(&folder_path)?;
 		let (object_points_root, point_objects_root) = roots_log.roots;
 		let object_points = Hamt::new(object_points_root);
 		let point_objects = Hamt::new(point_objects_root);
-		InnerEcho { diary, diary_writer, object_points, point_objects, roots_log }
-	}
-}
-
-struct RootsLog {
-	appender: File,
-	roots: (Root, Root),
-}
-
-impl RootsLog {
-	pub fn write_roots(&mut self, a: Root, b: Root) -> io::Result<()> {
-		let pos = self.appender.seek(SeekFrom::Current(0))?;
-		let result = a.write_bytes(&mut self.appender)
-			.and_then(|len| {
-				assert_eq!(len, ROOT_LEN);
-				b.write_bytes(&mut self.appender)
-			})
-			.map(|len| {
-				assert_eq!(len, ROOT_LEN);
-				()
-			});
-		if result.is_err() {
-			self.appender.set_len(pos).unwrap();
-			self.appender.seek(SeekFrom::Start(pos)).unwrap();
+		if options.locking == Locking::Coordinated {
+			lock_file.unlock()?;
 		}
-		result
-	}
-	pub fn new(folder_path: &Path) -> io::Result<Self> {
-		let file_path = file_path("roots.dat", folder_path);
-		let appender = OpenOptions::new().create(true).append(true).open(&file_path)?;
-		let roots = {
-			let file_len = std::fs::metadata(&file_path)?.len();
-			if file_len == 0 {
-				(Root::ZERO, Root::ZERO)
-			} else {
-				let mut reader = OpenOptions::new().read(true).open(&file_path)?;
-				reader.seek(SeekFrom::End(-2 * hamt::ROOT_LEN as i64))?;
-				let a_root = Root::read_bytes(&mut reader)?;
-				let b_root = Root::read_bytes(&mut reader)?;
-				(a_root, b_root)
-			}
-		};
-		Ok(RootsLog { appender, roots })
+		let published = Published::new(diary.reader()?, file_path("roots.dat", &folder_path), Snapshot {
+			roots: roots_log.roots,
+			diary_end: diary.file_size.get(),
+			version: roots_log.version,
+		});
+		Ok(InnerEcho {
+			diary,
+			diary_writer,
+			object_points,
+			point_objects,
+			roots_log,
+			subscribers: Vec::new(),
+			durability: options.durability,
+			recovery,
+			folder_path,
+			lock_file,
+			locking: options.locking,
+			published: Arc::new(published),
+		})
 	}
 }
 
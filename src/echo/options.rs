@@ -0,0 +1,63 @@
+/// `EchoOptions` configures a connection to an Echo.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct EchoOptions {
+	pub durability: Durability,
+	pub locking: Locking,
+	pub cache: CacheOptions,
+}
+
+impl EchoOptions {
+	pub fn durability(mut self, durability: Durability) -> Self {
+		self.durability = durability;
+		self
+	}
+
+	pub fn locking(mut self, locking: Locking) -> Self {
+		self.locking = locking;
+		self
+	}
+
+	pub fn cache(mut self, cache: CacheOptions) -> Self {
+		self.cache = cache;
+		self
+	}
+}
+
+/// `CacheOptions` limits the counts of decoded HAMT frames and diary values the Echo's
+/// chambers share. A limit of zero turns that part of the cache off.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CacheOptions {
+	pub frames: usize,
+	pub values: usize,
+}
+
+impl Default for CacheOptions {
+	fn default() -> Self {
+		CacheOptions { frames: 1024, values: 8192 }
+	}
+}
+
+/// `Durability` selects when the writer flushes commits to stable storage.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Durability {
+	/// Commits are left to the operating system to flush.
+	#[default]
+	None,
+	/// Each commit is synced before its writer is answered.
+	SyncEachCommit,
+	/// Commits queued while the writer is busy are applied together and synced once
+	/// before their writers are answered.
+	GroupCommit,
+}
+
+/// `Locking` selects how a writer shares the Echo's folder with other processes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Locking {
+	/// The writer holds the folder's lock while open. Connecting fails when another
+	/// process holds the lock.
+	#[default]
+	Exclusive,
+	/// The writer takes the folder's lock for each commit and first reloads the commits
+	/// of other coordinated writers.
+	Coordinated,
+}
@@ -0,0 +1,51 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::{PoisonError, RwLock};
+
+use crate::{Chamber, diary};
+use crate::diary::CacheStats;
+use crate::echo::roots_log::RootsReader;
+use crate::hamt::{Hamt, Root};
+
+/// `Published` holds the latest flushed commit of a writer so Echo handles can construct
+/// chambers without messaging the writer thread.
+#[derive(Debug)]
+pub(crate) struct Published {
+	diary_reader: diary::Reader,
+	roots_path: PathBuf,
+	latest: RwLock<Snapshot>,
+}
+
+/// The roots, diary length and version of a flushed commit.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Snapshot {
+	pub roots: (Root, Root),
+	pub diary_end: usize,
+	pub version: u64,
+}
+
+impl Published {
+	pub fn publish(&self, snapshot: Snapshot) {
+		*self.latest.write().unwrap_or_else(PoisonError::into_inner) = snapshot;
+	}
+
+	pub fn chamber(&self) -> io::Result<Chamber> {
+		let snapshot = *self.latest.read().unwrap_or_else(PoisonError::into_inner);
+		let chamber = Chamber {
+			object_points_reader: Hamt::new(snapshot.roots.0).reader()?,
+			point_objects_reader: Hamt::new(snapshot.roots.1).reader()?,
+			diary_reader: self.diary_reader.with_size(snapshot.diary_end),
+			roots_reader: RootsReader::new(self.roots_path.to_owned()),
+			version: snapshot.version,
+		};
+		Ok(chamber)
+	}
+
+	pub fn cache_stats(&self) -> Option<CacheStats> {
+		self.diary_reader.cache().map(|cache| cache.stats())
+	}
+
+	pub fn new(diary_reader: diary::Reader, roots_path: PathBuf, snapshot: Snapshot) -> Self {
+		Published { diary_reader, roots_path, latest: RwLock::new(snapshot) }
+	}
+}
@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Chamber, diary, EchoError};
+use crate::echo::file_path;
+use crate::echo::recovery::last_good_commit;
+use crate::echo::roots_log::{Commit, RootsReader};
+use crate::echo::subscription::{subscription, Subscription};
+use crate::hamt::{Hamt, Root};
+
+/// `ReadOnlyEcho` reads the commits of an Echo written by another process. It opens
+/// the Echo's files only for reading and never repairs or locks them. Clones share the
+/// open files and the diary's map.
+#[derive(Debug, Clone)]
+pub struct ReadOnlyEcho {
+	follower: Arc<Mutex<Follower>>,
+}
+
+impl ReadOnlyEcho {
+	/// Constructs a chamber for reading facts as of the latest commit found in `roots.dat`.
+	pub fn chamber(&self) -> Result<Chamber, EchoError> {
+		let mut follower = self.follower.lock().unwrap_or_else(PoisonError::into_inner);
+		let (version, commit) = follower.latest_commit()?;
+		Ok(follower.chamber(version, commit)?)
+	}
+
+	/// Checks `roots.dat` for new commits at the given interval and sends a chamber for
+	/// the latest commit each time one appears. Stops at the first check after the
+	/// subscription is dropped.
+	pub fn watch(&self, interval: Duration) -> Subscription<Chamber> {
+		let (subscriber, subscription) = subscription::<Chamber>();
+		let follower = self.follower.clone();
+		let mut version = follower.lock().unwrap_or_else(PoisonError::into_inner).latest_commit().map_or(0, |(version, _)| version);
+		thread::spawn(move || {
+			while subscriber.is_alive() {
+				thread::sleep(interval);
+				let mut follower = follower.lock().unwrap_or_else(PoisonError::into_inner);
+				// A failed read is retried at the next interval.
+				let chamber = match follower.latest_commit() {
+					Ok((latest, commit)) if latest > version => follower.chamber(latest, commit).map(|chamber| (latest, chamber)),
+					_ => continue,
+				};
+				drop(follower);
+				if let Ok((latest, chamber)) = chamber {
+					version = latest;
+					if !subscriber.send(chamber) {
+						break;
+					}
+				}
+			}
+		});
+		subscription
+	}
+
+	pub(crate) fn open(folder_path: PathBuf) -> io::Result<Self> {
+		let echo = ReadOnlyEcho { follower: Arc::new(Mutex::new(Follower::new(&folder_path)?)) };
+		echo.chamber()?;
+		Ok(echo)
+	}
+}
+
+/// `Follower` finds the latest commit of an Echo, keeping the roots file and the diary
+/// reader open from one check to the next.
+#[derive(Debug)]
+struct Follower {
+	diary_path: PathBuf,
+	roots_path: PathBuf,
+	roots_file: File,
+	diary_reader: diary::Reader,
+}
+
+impl Follower {
+	fn new(folder_path: &Path) -> io::Result<Self> {
+		let diary_path = file_path("diary.dat", folder_path);
+		let roots_path = file_path("roots.dat", folder_path);
+		let roots_file = File::open(&roots_path)?;
+		let diary_reader = diary::Reader::new(&diary_path, 0)?;
+		Ok(Follower { diary_path, roots_path, roots_file, diary_reader })
+	}
+
+	/// Finds the last good commit and the count of commits up to it.
+	fn latest_commit(&mut self) -> io::Result<(u64, Option<Commit>)> {
+		let diary_len = std::fs::metadata(&self.diary_path)?.len();
+		last_good_commit(&mut self.roots_file, diary_len)
+	}
+
+	/// Constructs a chamber for a commit from the shared diary reader. The diary is
+	/// remapped only when the commit ends beyond the reader's map.
+	fn chamber(&mut self, version: u64, commit: Option<Commit>) -> io::Result<Chamber> {
+		let (object_points_root, point_objects_root) = commit.map_or((Root::ZERO, Root::ZERO), |commit| commit.roots);
+		let diary_end = commit.map_or(0, |commit| commit.diary_end) as usize;
+		self.diary_reader = self.diary_reader.with_size(diary_end);
+		let chamber = Chamber {
+			object_points_reader: Hamt::new(object_points_root).reader()?,
+			point_objects_reader: Hamt::new(point_objects_root).reader()?,
+			diary_reader: self.diary_reader.clone(),
+			roots_reader: RootsReader::new(self.roots_path.to_owned()),
+			version,
+		};
+		Ok(chamber)
+	}
+}
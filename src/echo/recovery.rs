@@ -0,0 +1,85 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::bytes::ReadBytes;
+use crate::EchoError;
+use crate::echo::roots_log::{check_header, Commit, COMMIT_LEN, commit_count, commit_offset, header, HEADER_LEN};
+
+/// `Recovery` reports the repairs made when an Echo was opened after an interrupted commit.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Recovery {
+	/// Bytes of a partially written commit dropped from the end of the roots log.
+	pub torn_bytes: u64,
+	/// Whole commits dropped from the end of the roots log because their checksum failed
+	/// or they pointed beyond the diary.
+	pub dropped_commits: u64,
+	/// Diary bytes written after the last good commit and dropped.
+	pub truncated_diary_bytes: u64,
+}
+
+impl Recovery {
+	/// Checks that nothing needed repair.
+	pub fn is_clean(&self) -> bool { self == &Recovery::default() }
+}
+
+/// Writes the header of a new roots log, drops a torn or invalid tail after the last good
+/// commit, and truncates the diary to the end of that commit. Refuses files written in
+/// another format, and reports corruption when a log with whole commits has no good one.
+pub(crate) fn recover(roots_path: &Path, diary_path: &Path) -> io::Result<Recovery> {
+	let mut roots_file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(roots_path)?;
+	let diary_file = OpenOptions::new().write(true).create(true).truncate(false).open(diary_path)?;
+	let diary_len = diary_file.metadata()?.len();
+	check_header(&mut roots_file)?;
+	let mut roots_len = roots_file.metadata()?.len();
+	if roots_len < HEADER_LEN {
+		// Only a writer creating the log leaves it without a header, and it writes the
+		// header before the diary.
+		if diary_len > 0 {
+			return Err(io::Error::other(EchoError::UnsupportedFormat));
+		}
+		roots_file.set_len(0)?;
+		roots_file.seek(SeekFrom::Start(0))?;
+		roots_file.write_all(&header())?;
+		roots_file.sync_data()?;
+		roots_len = HEADER_LEN;
+	}
+	let whole_commits = commit_count(roots_len);
+	let (good_commits, last_commit) = last_good_commit(&mut roots_file, diary_len)?;
+	if whole_commits > 0 && good_commits == 0 {
+		return Err(io::Error::new(ErrorKind::InvalidData, "No commit in roots.dat is intact"));
+	}
+	let diary_end = last_commit.map_or(0, |commit| commit.diary_end);
+	let good_len = HEADER_LEN + good_commits * COMMIT_LEN;
+	if good_len < roots_len {
+		roots_file.set_len(good_len)?;
+		roots_file.sync_data()?;
+	}
+	if diary_end < diary_len {
+		diary_file.set_len(diary_end)?;
+		diary_file.sync_data()?;
+	}
+	Ok(Recovery {
+		torn_bytes: (roots_len - HEADER_LEN) % COMMIT_LEN,
+		dropped_commits: whole_commits - good_commits,
+		truncated_diary_bytes: diary_len - diary_end,
+	})
+}
+
+/// Finds the last whole commit in the roots file that passes its checksum and fits in a
+/// diary of the given length. Returns the count of commits up to it and the commit.
+pub(crate) fn last_good_commit(roots_file: &mut File, diary_len: u64) -> io::Result<(u64, Option<Commit>)> {
+	check_header(roots_file)?;
+	let mut good_commits = commit_count(roots_file.metadata()?.len());
+	while good_commits > 0 {
+		roots_file.seek(SeekFrom::Start(commit_offset(good_commits)))?;
+		match Commit::read_bytes(roots_file) {
+			Ok(commit) if commit.fits(diary_len) => return Ok((good_commits, Some(commit))),
+			Ok(_) => good_commits -= 1,
+			Err(e) if e.kind() == ErrorKind::InvalidData => good_commits -= 1,
+			Err(e) => return Err(e),
+		}
+	}
+	Ok((0, None))
+}
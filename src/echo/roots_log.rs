@@ -0,0 +1,292 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use crate::bytes::{ReadBytes, WriteBytes};
+use crate::{diary, EchoError};
+use crate::echo::file_path;
+use crate::echo::recovery::last_good_commit;
+use crate::hamt::{Root, ROOT_LEN};
+use crate::util::crc32;
+
+#[cfg(test)]
+mod tests {
+	use std::error::Error;
+	use std::fs::File;
+	use std::io::Write;
+
+	use crate::diary;
+	use crate::echo::roots_log::{check_header, Commit, header, RootsLog};
+	use crate::hamt::Root;
+	use crate::util::temp_dir;
+
+	fn commit(n: usize) -> Commit {
+		Commit { roots: (Root::ZERO, Root::ZERO), speech: diary::Pos::at(n), diary_end: n as u64 + 1 }
+	}
+
+	#[test]
+	fn failed_first_flush_after_reopen_keeps_earlier_commits() -> Result<(), Box<dyn Error>> {
+		let folder_path = temp_dir("roots-log")?;
+		File::create(folder_path.join("roots.dat"))?.write_all(&header())?;
+		let mut roots_log = RootsLog::new(&folder_path)?;
+		roots_log.stage_commit(commit(1));
+		roots_log.stage_commit(commit(2));
+		roots_log.flush(true)?;
+		let mut roots_log = RootsLog::new(&folder_path)?;
+		roots_log.fail_writes = true;
+		roots_log.stage_commit(commit(3));
+		assert!(roots_log.flush(true).is_err());
+		assert_eq!(roots_log.version, 2);
+		let mut roots_log = RootsLog::new(&folder_path)?;
+		check_header(&mut File::open(folder_path.join("roots.dat"))?)?;
+		assert_eq!(roots_log.version, 2);
+		assert_eq!(roots_log.read_commit(2)?, commit(2));
+		Ok(())
+	}
+}
+
+/// `RootsLog` appends a `Commit` to `roots.dat` for each commit. The version of a commit
+/// is its one-based position among the commits after the file's header. Commits are staged until flushed so a group
+/// of commits reaches the file after the diary bytes they point to.
+pub(crate) struct RootsLog {
+	file_path: PathBuf,
+	appender: File,
+	reader: File,
+	staged: Vec<Commit>,
+	flushed_roots: (Root, Root),
+	poisoned: bool,
+	pub roots: (Root, Root),
+	pub version: u64,
+	#[cfg(test)]
+	pub fail_writes: bool,
+}
+
+/// `Commit` holds the object-points and point-objects roots produced by a commit, the
+/// diary position of the commit's transaction record, and the diary length after the commit.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Commit {
+	pub roots: (Root, Root),
+	pub speech: diary::Pos,
+	pub diary_end: u64,
+}
+
+impl Commit {
+	/// Checks the commit points only at bytes inside a diary of the given length.
+	pub fn fits(&self, diary_len: u64) -> bool {
+		let inside = |root: &Root| root.mask == 0 || (root.pos as u64) < self.diary_end;
+		self.diary_end <= diary_len
+			&& u64::from(self.speech) < self.diary_end
+			&& inside(&self.roots.0)
+			&& inside(&self.roots.1)
+	}
+}
+
+impl WriteBytes for Commit {
+	/// Writes the commit followed by a checksum of its bytes.
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		let mut body = Vec::with_capacity(COMMIT_LEN as usize);
+		self.roots.0.write_bytes(&mut body)?;
+		self.roots.1.write_bytes(&mut body)?;
+		u64::from(self.speech).write_bytes(&mut body)?;
+		self.diary_end.write_bytes(&mut body)?;
+		crc32(&body).write_bytes(&mut body)?;
+		writer.write_all(&body)?;
+		Ok(body.len())
+	}
+}
+
+impl ReadBytes<Commit> for Commit {
+	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
+		let mut body = [0u8; COMMIT_LEN as usize];
+		reader.read_exact(&mut body)?;
+		let (data, checksum) = body.split_at(COMMIT_LEN as usize - 4);
+		if crc32(data) != u32::read_bytes(&mut Cursor::new(checksum))? {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Commit checksum mismatch"));
+		}
+		let mut data = Cursor::new(data);
+		let a_root = Root::read_bytes(&mut data)?;
+		let b_root = Root::read_bytes(&mut data)?;
+		let speech = diary::Pos::at(u64::read_bytes(&mut data)? as usize);
+		let diary_end = u64::read_bytes(&mut data)?;
+		Ok(Commit { roots: (a_root, b_root), speech, diary_end })
+	}
+}
+
+/// `RootsReader` reads the commits recorded in `roots.dat`.
+#[derive(Debug, Clone)]
+pub(crate) struct RootsReader {
+	file_path: PathBuf,
+}
+
+impl RootsReader {
+	pub fn new(file_path: PathBuf) -> Self { RootsReader { file_path } }
+
+	/// Reads the commits with the given versions, oldest first.
+	pub fn read_commits(&self, versions: RangeInclusive<u64>) -> io::Result<Vec<Commit>> {
+		let (first, last) = (*versions.start(), *versions.end());
+		if first > last {
+			return Ok(Vec::new());
+		}
+		let mut file = OpenOptions::new().read(true).open(&self.file_path)?;
+		file.seek(SeekFrom::Start(commit_offset(first)))?;
+		let mut bytes = vec![0u8; ((last - first + 1) * COMMIT_LEN) as usize];
+		file.read_exact(&mut bytes)?;
+		let mut cursor = Cursor::new(bytes);
+		versions.map(|_| Commit::read_bytes(&mut cursor)).collect()
+	}
+}
+
+impl RootsLog {
+	/// Adds a commit to the log. The commit is written to the file by the next flush.
+	pub fn stage_commit(&mut self, commit: Commit) {
+		self.staged.push(commit);
+		self.roots = commit.roots;
+		self.version += 1;
+	}
+
+	/// Writes the staged commits to the file, syncing the file when asked. On failure the
+	/// staged commits are dropped, the log returns to its last flushed commit, and the
+	/// partial write is cut from the file. When the cut fails, the log refuses further
+	/// flushes, since commits appended after the partial write would be misplaced. The
+	/// caller holds the commit lock, so the file's length is where the commits go.
+	pub fn flush(&mut self, sync: bool) -> io::Result<()> {
+		if self.staged.is_empty() {
+			return Ok(());
+		}
+		if self.poisoned {
+			self.discard_staged();
+			return Err(io::Error::other("roots.dat ends with a partial commit"));
+		}
+		let pos = match self.appender.metadata() {
+			Ok(metadata) => metadata.len(),
+			Err(e) => {
+				self.discard_staged();
+				return Err(e);
+			}
+		};
+		match self.write_staged(sync) {
+			Ok(()) => {
+				self.staged.clear();
+				self.flushed_roots = self.roots;
+				Ok(())
+			}
+			Err(e) => {
+				self.discard_staged();
+				if let Err(cut_error) = self.appender.set_len(pos) {
+					self.poisoned = true;
+					return Err(cut_error);
+				}
+				Err(e)
+			}
+		}
+	}
+
+	/// Catches up with commits appended by other writers, stopping at the last good commit
+	/// for a diary of the given length. Returns that commit.
+	pub fn reload(&mut self, diary_len: u64) -> io::Result<Option<Commit>> {
+		debug_assert!(self.staged.is_empty());
+		let (version, commit) = last_good_commit(&mut self.reader, diary_len)?;
+		self.version = version;
+		self.roots = commit.map_or((Root::ZERO, Root::ZERO), |commit| commit.roots);
+		self.flushed_roots = self.roots;
+		Ok(commit)
+	}
+
+	/// Flushes the written commits to stable storage.
+	pub fn sync(&self) -> io::Result<()> { self.appender.sync_data() }
+
+	/// Drops the staged commits and returns the log to its last flushed commit.
+	pub fn discard_staged(&mut self) {
+		self.version -= self.staged.drain(..).count() as u64;
+		self.roots = self.flushed_roots;
+	}
+
+	fn write_staged(&mut self, sync: bool) -> io::Result<()> {
+		let mut bytes = Vec::with_capacity(self.staged.len() * COMMIT_LEN as usize);
+		for commit in &self.staged {
+			let len = commit.write_bytes(&mut bytes)?;
+			assert_eq!(len as u64, COMMIT_LEN);
+		}
+		#[cfg(test)]
+		if self.fail_writes {
+			self.appender.write_all(&bytes[..bytes.len() / 2])?;
+			return Err(io::Error::other("Failed write"));
+		}
+		self.appender.write_all(&bytes)?;
+		if sync {
+			self.appender.sync_data()?;
+		}
+		Ok(())
+	}
+
+	/// Reads a past commit.
+	pub fn read_commit(&mut self, version: u64) -> io::Result<Commit> {
+		if version == 0 || version > self.version {
+			return Err(io::Error::new(ErrorKind::InvalidInput, format!("No commit with version {}", version)));
+		}
+		let flushed = self.version - self.staged.len() as u64;
+		if version > flushed {
+			return Ok(self.staged[(version - flushed - 1) as usize]);
+		}
+		self.reader.seek(SeekFrom::Start(commit_offset(version)))?;
+		Commit::read_bytes(&mut self.reader)
+	}
+
+	pub fn reader(&self) -> RootsReader {
+		RootsReader::new(self.file_path.to_owned())
+	}
+
+	pub fn new(folder_path: &Path) -> io::Result<Self> {
+		let file_path = file_path("roots.dat", folder_path);
+		let appender = OpenOptions::new().create(true).append(true).open(&file_path)?;
+		let reader = OpenOptions::new().read(true).open(&file_path)?;
+		let version = commit_count(std::fs::metadata(&file_path)?.len());
+		let mut roots_log = RootsLog { file_path, appender, reader, staged: Vec::new(), flushed_roots: (Root::ZERO, Root::ZERO), poisoned: false, roots: (Root::ZERO, Root::ZERO), version, #[cfg(test)] fail_writes: false };
+		if version > 0 {
+			roots_log.roots = roots_log.read_commit(version)?.roots;
+			roots_log.flushed_roots = roots_log.roots;
+		}
+		Ok(roots_log)
+	}
+}
+
+pub(crate) const COMMIT_LEN: u64 = 2 * ROOT_LEN as u64 + 8 + 8 + 4;
+
+/// `roots.dat` starts with a header of `ROOTS_MAGIC` and `FORMAT_VERSION`. The version
+/// covers the layout of both `roots.dat` and `diary.dat`.
+pub(crate) const HEADER_LEN: u64 = 8;
+const ROOTS_MAGIC: [u8; 4] = *b"ECHO";
+const FORMAT_VERSION: u32 = 1;
+
+pub(crate) fn header() -> [u8; HEADER_LEN as usize] {
+	let mut header = [0u8; HEADER_LEN as usize];
+	header[..4].copy_from_slice(&ROOTS_MAGIC);
+	header[4..].copy_from_slice(&FORMAT_VERSION.to_be_bytes());
+	header
+}
+
+/// Checks that the roots file was written in this format. A file shorter than the
+/// header passes when it holds the start of the header, as a writer interrupted while
+/// creating the file leaves it.
+pub(crate) fn check_header(roots_file: &mut File) -> io::Result<()> {
+	let len = roots_file.metadata()?.len().min(HEADER_LEN) as usize;
+	let mut bytes = [0u8; HEADER_LEN as usize];
+	roots_file.seek(SeekFrom::Start(0))?;
+	roots_file.read_exact(&mut bytes[..len])?;
+	match bytes[..len] == header()[..len] {
+		true => Ok(()),
+		false => Err(io::Error::other(EchoError::UnsupportedFormat)),
+	}
+}
+
+/// Returns the position in the roots file of the commit with the version.
+pub(crate) fn commit_offset(version: u64) -> u64 {
+	HEADER_LEN + (version - 1) * COMMIT_LEN
+}
+
+/// Returns the count of whole commits in a roots file of the length.
+pub(crate) fn commit_count(roots_len: u64) -> u64 {
+	roots_len.saturating_sub(HEADER_LEN) / COMMIT_LEN
+}
@@ -0,0 +1,69 @@
+use std::ops::Deref;
+use std::sync::{Arc, Weak};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::{Chamber, ObjectId, Point, Say};
+
+/// `Change` tells a subscriber about a commit that applied says it is interested in.
+pub struct Change {
+	pub chamber: Chamber,
+	pub version: u64,
+	pub says: Vec<Say>,
+}
+
+/// Selects the says of a commit a subscriber is told about.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ChangeFilter {
+	All,
+	Point(Point),
+	Object(ObjectId),
+}
+
+impl ChangeFilter {
+	pub(crate) fn select(&self, says: &[Say]) -> Vec<Say> {
+		says.iter().filter(|say| self.selects(say)).cloned().collect()
+	}
+
+	pub(crate) fn selects_any(&self, says: &[Say]) -> bool {
+		says.iter().any(|say| self.selects(say))
+	}
+
+	fn selects(&self, say: &Say) -> bool {
+		match self {
+			ChangeFilter::All => true,
+			ChangeFilter::Point(point) => &say.point == point,
+			ChangeFilter::Object(object) => &say.object == object,
+		}
+	}
+}
+
+/// `Subscription` receives the values sent to a subscriber. Dropping it ends the
+/// subscription, and the sender forgets the subscriber before its next send.
+pub struct Subscription<T> {
+	rx: Receiver<T>,
+	_alive: Arc<()>,
+}
+
+impl<T> Deref for Subscription<T> {
+	type Target = Receiver<T>;
+	fn deref(&self) -> &Self::Target { &self.rx }
+}
+
+/// `Subscriber` sends values to a `Subscription` for as long as the subscription lives.
+pub(crate) struct Subscriber<T> {
+	tx: Sender<T>,
+	alive: Weak<()>,
+}
+
+impl<T> Subscriber<T> {
+	pub fn is_alive(&self) -> bool { self.alive.strong_count() > 0 }
+
+	/// Sends the value and reports whether the subscription is still listening.
+	pub fn send(&self, value: T) -> bool { self.tx.send(value).is_ok() }
+}
+
+pub(crate) fn subscription<T>() -> (Subscriber<T>, Subscription<T>) {
+	let (tx, rx) = channel();
+	let alive = Arc::new(());
+	(Subscriber { tx, alive: Arc::downgrade(&alive) }, Subscription { rx, _alive: alive })
+}
@@ -0,0 +1,99 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+use crate::{Chamber, EchoError, ObjectId, Point, Sayer, Speech, Target};
+use crate::echo::WriteScope;
+
+/// A `Transaction` reads from the chamber it started with, overlaid with its own pending
+/// says, and writes through its `WriteScope`. Every read that reaches the chamber is
+/// recorded so the commit can be refused when a concurrent commit has changed those facts.
+pub struct Transaction {
+	chamber: Chamber,
+	scope: WriteScope,
+	observations: Vec<Observation>,
+}
+
+/// A fact read by a transaction from its chamber.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum Observation {
+	Target { object: ObjectId, point: Point, target: Option<Target> },
+	Objects { point: Point, objects: Vec<ObjectId> },
+}
+
+impl Observation {
+	/// Checks the observation still holds in the chamber.
+	pub fn holds(&self, chamber: &Chamber) -> io::Result<bool> {
+		let holds = match self {
+			Observation::Target { object, point, target } => &chamber.read_target(object, point)? == target,
+			Observation::Objects { point, objects } => &sorted(chamber.objects_with_point(point)?) == objects,
+		};
+		Ok(holds)
+	}
+}
+
+impl Transaction {
+	/// Reads the target at a point on an object, including targets said earlier in the transaction.
+	pub fn target_at_object_point_or_none(&mut self, object: &ObjectId, point: &Point) -> Result<Option<Target>, EchoError> {
+		let pending = self.scope.says.iter().rev()
+			.find(|say| &say.object == object && &say.point == point)
+			.map(|say| say.target.to_owned());
+		if let Some(target) = pending {
+			return Ok(target);
+		}
+		if self.scope.retracted_objects.contains(object) {
+			return Ok(None);
+		}
+		let target = self.chamber.read_target(object, point)?;
+		self.observations.push(Observation::Target { object: object.to_owned(), point: point.to_owned(), target: target.to_owned() });
+		Ok(target)
+	}
+
+	/// Lists the objects holding a target at a point, including targets said earlier in the transaction.
+	pub fn objects_with_point(&mut self, point: &Point) -> Result<Vec<ObjectId>, EchoError> {
+		let mut objects = sorted(self.chamber.objects_with_point(point)?);
+		self.observations.push(Observation::Objects { point: point.to_owned(), objects: objects.to_owned() });
+		objects.retain(|object| !self.scope.retracted_objects.contains(object));
+		for say in self.scope.says.iter().filter(|say| &say.point == point) {
+			match say.target {
+				None => objects.retain(|object| object != &say.object),
+				Some(_) => if !objects.contains(&say.object) {
+					objects.push(say.object.to_owned())
+				},
+			}
+		}
+		Ok(objects)
+	}
+
+	/// Lists the objects holding the target at a point, including targets said earlier in the transaction.
+	pub fn objects_with_property(&mut self, point: &Point, target: &Target) -> Result<Vec<ObjectId>, EchoError> {
+		let mut matching_objects = Vec::new();
+		for object in self.objects_with_point(point)? {
+			if self.target_at_object_point_or_none(&object, point)?.as_ref() == Some(target) {
+				matching_objects.push(object)
+			}
+		}
+		Ok(matching_objects)
+	}
+
+	pub(crate) fn into_parts(self) -> (Speech, Vec<Observation>) {
+		(self.scope.into_speech(), self.observations)
+	}
+
+	pub(crate) fn new(chamber: Chamber, sayer: &Sayer) -> Self {
+		Transaction { chamber, scope: WriteScope::new(sayer), observations: Vec::new() }
+	}
+}
+
+impl Deref for Transaction {
+	type Target = WriteScope;
+	fn deref(&self) -> &Self::Target { &self.scope }
+}
+
+impl DerefMut for Transaction {
+	fn deref_mut(&mut self) -> &mut Self::Target { &mut self.scope }
+}
+
+fn sorted(mut objects: Vec<ObjectId>) -> Vec<ObjectId> {
+	objects.sort();
+	objects
+}
@@ -0,0 +1,89 @@
+use std::io;
+use std::io::{Cursor, Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{diary, EchoError, Point, Say, Sayer, Target};
+use crate::bytes::{ReadBytes, WriteBytes};
+use crate::echo::roots_log::Commit;
+
+/// `TransactionRecord` is the diary's account of a commit: the says it applied, when
+/// and by whom they were said, and any metadata facts supplied with them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TransactionRecord {
+	pub version: u64,
+	/// Milliseconds since the Unix epoch.
+	pub timestamp: u64,
+	pub sayer: Sayer,
+	pub says: Vec<Say>,
+	pub metadata: Vec<(Point, Target)>,
+}
+
+impl TransactionRecord {
+	pub(crate) fn now_millis() -> u64 {
+		SystemTime::now().duration_since(UNIX_EPOCH)
+			.map(|duration| duration.as_millis() as u64)
+			.unwrap_or(0)
+	}
+}
+
+impl WriteBytes for TransactionRecord {
+	/// Writes the record framed by its length.
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		let mut body = Vec::new();
+		self.version.write_bytes(&mut body)?;
+		self.timestamp.write_bytes(&mut body)?;
+		self.sayer.write_bytes(&mut body)?;
+		(self.says.len() as u32).write_bytes(&mut body)?;
+		for say in &self.says {
+			say.write_bytes(&mut body)?;
+		}
+		(self.metadata.len() as u32).write_bytes(&mut body)?;
+		for (point, target) in &self.metadata {
+			point.write_bytes(&mut body)?;
+			target.write_bytes(&mut body)?;
+		}
+		let frame_len = (body.len() as u32).write_bytes(writer)?;
+		writer.write_all(&body)?;
+		Ok(frame_len + body.len())
+	}
+}
+
+impl ReadBytes<TransactionRecord> for TransactionRecord {
+	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
+		let body_len = u32::read_bytes(reader)?;
+		let mut body = vec![0u8; body_len as usize];
+		reader.read_exact(&mut body)?;
+		let mut body = Cursor::new(body);
+		let version = u64::read_bytes(&mut body)?;
+		let timestamp = u64::read_bytes(&mut body)?;
+		let sayer = Sayer::read_bytes(&mut body)?;
+		let says_count = u32::read_bytes(&mut body)?;
+		let says = (0..says_count)
+			.map(|_| Say::read_bytes(&mut body))
+			.collect::<io::Result<Vec<_>>>()?;
+		let metadata_count = u32::read_bytes(&mut body)?;
+		let metadata = (0..metadata_count)
+			.map(|_| Ok((Point::read_bytes(&mut body)?, Target::read_bytes(&mut body)?)))
+			.collect::<io::Result<Vec<_>>>()?;
+		Ok(TransactionRecord { version, timestamp, sayer, says, metadata })
+	}
+}
+
+/// Iterator over the transaction records of a range of commits, oldest first.
+pub struct Transactions {
+	commits: std::vec::IntoIter<Commit>,
+	diary_reader: diary::Reader,
+}
+
+impl Transactions {
+	pub(crate) fn new(commits: Vec<Commit>, diary_reader: diary::Reader) -> Self {
+		Transactions { commits: commits.into_iter(), diary_reader }
+	}
+}
+
+impl Iterator for Transactions {
+	type Item = Result<TransactionRecord, EchoError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		self.commits.next().map(|commit| Ok(self.diary_reader.read::<TransactionRecord>(commit.speech)?))
+	}
+}
@@ -0,0 +1,39 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::EchoError;
+
+/// Error produced by `Echo::try_write` when the write function refuses the speech or
+/// the database fails to write it.
+#[derive(Debug)]
+pub enum WriteError<E> {
+	Echo(EchoError),
+	Aborted(E),
+}
+
+impl<E: fmt::Display> fmt::Display for WriteError<E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			WriteError::Echo(e) => write!(f, "Write failed: {}", e),
+			WriteError::Aborted(e) => write!(f, "Write aborted: {}", e),
+		}
+	}
+}
+
+impl<E: Error + 'static> Error for WriteError<E> {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			WriteError::Echo(e) => Some(e),
+			WriteError::Aborted(e) => Some(e),
+		}
+	}
+}
+
+impl<E> From<EchoError> for WriteError<E> {
+	fn from(e: EchoError) -> Self { WriteError::Echo(e) }
+}
+
+impl<E> From<io::Error> for WriteError<E> {
+	fn from(e: io::Error) -> Self { WriteError::Echo(e.into()) }
+}
@@ -1,11 +1,27 @@
-use crate::{ObjectId, Point, Say, Sayer, Target, Writable};
+use crate::{ObjectId, Point, Say, Sayer, Speech, Target, Writable};
 use crate::util::unique_name;
 
 pub struct WriteScope {
-	pub says: Vec<Say>
+	pub says: Vec<Say>,
+	pub(crate) retracted_objects: Vec<ObjectId>,
+	sayer: Sayer,
+	metadata: Vec<(Point, Target)>,
 }
 
 impl WriteScope {
+	pub(crate) fn new(sayer: &Sayer) -> Self {
+		WriteScope { says: Vec::new(), retracted_objects: Vec::new(), sayer: sayer.to_owned(), metadata: Vec::new() }
+	}
+
+	pub(crate) fn into_speech(self) -> Speech {
+		Speech { sayer: self.sayer, says: self.says, retracted_objects: self.retracted_objects, metadata: self.metadata, bulk: false }
+	}
+
+	/// Adds a fact about the scope itself to its transaction record.
+	pub fn metadata(&mut self, point: &Point, target: Target) {
+		self.metadata.push((point.to_owned(), target))
+	}
+
 	pub fn new_object_id(&self, prefix: &str) -> ObjectId { ObjectId::String(unique_name(prefix)) }
 
 	pub fn writable(&mut self, writable: &impl Writable) {
@@ -14,7 +30,7 @@ impl WriteScope {
 
 	pub fn write_object_properties(&mut self, object: &ObjectId, properties: Vec<(&Point, Target)>) {
 		for (point, target) in properties {
-			let say = Say { sayer: Sayer::Unit, object: object.to_owned(), point: point.to_owned(), target: Some(target) };
+			let say = Say { sayer: self.sayer.to_owned(), object: object.to_owned(), point: point.to_owned(), target: Some(target) };
 			self.says.push(say)
 		}
 	}
@@ -27,7 +43,24 @@ impl WriteScope {
 		self.attributes(vec![(&Point::Unit, target)])
 	}
 
+	/// Removes the target at a point on an object.
+	pub fn retract(&mut self, object: &ObjectId, point: &Point) {
+		let say = Say { sayer: self.sayer.to_owned(), object: object.to_owned(), point: point.to_owned(), target: None };
+		self.says.push(say)
+	}
+
+	/// Removes every target on an object. Says made earlier in the scope about the
+	/// object are dropped, and says made later are kept.
+	pub fn retract_object(&mut self, object: &ObjectId) {
+		self.says.retain(|say| &say.object != object);
+		if !self.retracted_objects.contains(object) {
+			self.retracted_objects.push(object.to_owned());
+		}
+	}
+
+	/// Attributes the says to the scope's sayer.
 	fn says(&mut self, says: Vec<Say>) {
-		self.says.extend(says);
+		let sayer = &self.sayer;
+		self.says.extend(says.into_iter().map(|say| Say { sayer: sayer.to_owned(), ..say }));
 	}
 }
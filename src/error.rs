@@ -0,0 +1,126 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+
+use crate::{EchoOptions, ObjectId, Point, Target};
+
+#[cfg(test)]
+mod tests {
+	use std::io;
+
+	use crate::EchoError;
+	use crate::error::duplicate_io_error;
+
+	#[test]
+	fn duplicates_keep_echo_variants_and_os_codes() {
+		let locked = io::Error::from(EchoError::Locked);
+		assert!(matches!(EchoError::from(duplicate_io_error(&locked)), EchoError::Locked));
+		let conflict = io::Error::from(EchoError::Conflict);
+		assert!(matches!(EchoError::from(duplicate_io_error(&conflict)), EchoError::Conflict));
+		let corruption = io::Error::new(io::ErrorKind::InvalidData, "Bad bytes");
+		assert!(matches!(EchoError::from(duplicate_io_error(&corruption)), EchoError::Corruption(_)));
+		let os_error = io::Error::from_raw_os_error(28);
+		assert_eq!(duplicate_io_error(&os_error).raw_os_error(), Some(28));
+	}
+}
+
+/// `EchoError` describes the ways an Echo operation can fail.
+#[derive(Debug)]
+pub enum EchoError {
+	/// Reading or writing the Echo's files failed.
+	Io(io::Error),
+	/// The Echo's files hold bytes that do not decode.
+	Corruption(String),
+	/// A target was read as a kind it is not.
+	TypeMismatch { expected: &'static str, target: Target },
+	/// No target is held at the point on the object.
+	MissingValue { object: ObjectId, point: Point },
+	/// Facts read by a transaction were changed by a concurrent commit.
+	Conflict,
+	/// The writer thread has stopped.
+	WriterGone,
+	/// Another writer holds the Echo's folder.
+	Locked,
+	/// The Echo's files were written in a format this version cannot read.
+	UnsupportedFormat,
+	/// The Echo is already open in this process with the held options.
+	OptionsMismatch(EchoOptions),
+}
+
+impl EchoError {
+	/// Constructs an error of the same variant, for sending one failure to several callers.
+	pub(crate) fn duplicate(&self) -> EchoError {
+		match self {
+			EchoError::Io(e) => EchoError::Io(duplicate_io_error(e)),
+			EchoError::Corruption(reason) => EchoError::Corruption(reason.to_owned()),
+			EchoError::TypeMismatch { expected, target } => EchoError::TypeMismatch { expected, target: target.to_owned() },
+			EchoError::MissingValue { object, point } => EchoError::MissingValue { object: object.to_owned(), point: point.to_owned() },
+			EchoError::Conflict => EchoError::Conflict,
+			EchoError::WriterGone => EchoError::WriterGone,
+			EchoError::Locked => EchoError::Locked,
+			EchoError::UnsupportedFormat => EchoError::UnsupportedFormat,
+			EchoError::OptionsMismatch(options) => EchoError::OptionsMismatch(*options),
+		}
+	}
+}
+
+/// Constructs an I/O error like the given one. An Echo error carried by the error is
+/// duplicated, and an operating system error keeps its code.
+pub(crate) fn duplicate_io_error(error: &io::Error) -> io::Error {
+	if let Some(echo_error) = error.get_ref().and_then(|inner| inner.downcast_ref::<EchoError>()) {
+		return io::Error::other(echo_error.duplicate());
+	}
+	match error.raw_os_error() {
+		Some(code) => io::Error::from_raw_os_error(code),
+		None => io::Error::new(error.kind(), error.to_string()),
+	}
+}
+
+impl fmt::Display for EchoError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			EchoError::Io(e) => write!(f, "I/O error: {}", e),
+			EchoError::Corruption(reason) => write!(f, "Corrupt Echo files: {}", reason),
+			EchoError::TypeMismatch { expected, target } => write!(f, "Target {:?} is not {}", target, expected),
+			EchoError::MissingValue { object, point } => write!(f, "No target at {:?} on {:?}", point, object),
+			EchoError::Conflict => f.write_str("Transaction read facts changed by a concurrent commit"),
+			EchoError::WriterGone => f.write_str("Echo writer has stopped"),
+			EchoError::Locked => f.write_str("Echo folder is locked by another writer"),
+			EchoError::UnsupportedFormat => f.write_str("Echo files are in an unsupported format"),
+			EchoError::OptionsMismatch(options) => write!(f, "Echo is already open with options {:?}", options),
+		}
+	}
+}
+
+impl Error for EchoError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			EchoError::Io(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for EchoError {
+	/// Recovers the Echo error carried by an I/O error and classifies undecodable bytes
+	/// as corruption.
+	fn from(error: io::Error) -> Self {
+		if error.get_ref().is_some_and(|inner| inner.is::<EchoError>()) {
+			return *error.into_inner().unwrap().downcast::<EchoError>().unwrap();
+		}
+		match error.kind() {
+			ErrorKind::InvalidData | ErrorKind::UnexpectedEof => EchoError::Corruption(error.to_string()),
+			_ => EchoError::Io(error),
+		}
+	}
+}
+
+impl From<EchoError> for io::Error {
+	fn from(error: EchoError) -> Self {
+		match error {
+			EchoError::Io(e) => e,
+			error => io::Error::other(error),
+		}
+	}
+}
@@ -1,4 +1,4 @@
-pub(crate) use self::read_write::{Reader, Writer, WriteSlot};
+pub(crate) use self::read_write::{read_slots, Reader, Writer, WriteSlot};
 pub(crate) use self::slot_index::SlotIndex;
 
 mod read_write;
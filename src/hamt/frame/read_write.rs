@@ -1,9 +1,11 @@
 use std::io;
+use std::io::Write;
 
+use crate::bytes::{ReadBytes, WriteBytes};
 use crate::diary;
-use crate::hamt::{frame, Root, slot};
+use crate::hamt::{frame, Root};
 use crate::hamt::frame::SlotIndex;
-use crate::hamt::slot::Slot;
+use crate::hamt::slot::{Slot, SLOT_LEN};
 
 #[cfg(test)]
 mod tests {
@@ -14,7 +16,7 @@ mod tests {
 	use crate::hamt::{frame, Root};
 	use crate::hamt::frame::read_write::WriteSlot;
 	use crate::hamt::frame::SlotIndex;
-	use crate::hamt::slot::Slot;
+	use crate::hamt::slot::{Slot, SLOT_LEN};
 
 	#[test]
 	fn revised_root() -> Result<(), Box<dyn Error>> {
@@ -24,19 +26,16 @@ mod tests {
 		let (path, root) = {
 			let diary = Diary::temp()?;
 			let mut diary_writer = diary.writer()?;
-			let mut writer = frame::Writer::new(&mut diary_writer);
 			// Write slot.
-			let mut first_diary_reader = diary.reader()?;
-			let mut first_reader = frame::Reader::new(Root::ZERO, &mut first_diary_reader)?;
-			let first_root = writer.write_revised_root(WriteSlot { slot: slot_a, slot_index }, &mut first_reader)?;
+			let first_reader = frame::Reader::new(Root::ZERO, &diary_writer.reader()?)?;
+			let first_root = frame::Writer::new(&mut diary_writer).write_revised_root(WriteSlot { slot: slot_a, slot_index }, &first_reader)?;
 			// Rewrite the slot.
-			let mut second_diary_reader = diary.reader()?;
-			let mut second_reader = frame::Reader::new(first_root, &mut second_diary_reader)?;
-			let second_root = writer.write_revised_root(WriteSlot { slot: slot_b, slot_index }, &mut second_reader)?;
+			let second_reader = frame::Reader::new(first_root, &diary_writer.reader()?)?;
+			let second_root = frame::Writer::new(&mut diary_writer).write_revised_root(WriteSlot { slot: slot_b, slot_index }, &second_reader)?;
 			(diary.file_path, second_root)
 		};
 		let mut slots = [Slot::Empty; 32];
-		slots[slot_index.n as usize] = slot_b;
+		slots[slot_index.n] = slot_b;
 		assert_slots(&path, root, &slots)
 	}
 
@@ -52,8 +51,8 @@ mod tests {
 			(diary.file_path, root)
 		};
 		let mut slots = [Slot::Empty; 32];
-		slots[write_slot1.slot_index.n as usize] = write_slot1.slot;
-		slots[write_slot7.slot_index.n as usize] = write_slot7.slot;
+		slots[write_slot1.slot_index.n] = write_slot1.slot;
+		slots[write_slot7.slot_index.n] = write_slot7.slot;
 		assert_slots(&path, root, &slots)
 	}
 
@@ -68,19 +67,17 @@ mod tests {
 			(diary.file_path, root)
 		};
 		let mut slots = [Slot::Empty; 32];
-		slots[write_slot1.slot_index.n as usize] = write_slot1.slot;
+		slots[write_slot1.slot_index.n] = write_slot1.slot;
 		assert_slots(&path, root, &slots)
 	}
 
 	fn assert_slots(path: &Path, root: Root, slots: &[Slot; 32]) -> Result<(), Box<dyn Error>> {
-		let diary = Diary::load(&path)?;
-		let mut diary_reader = diary.reader()?;
-		let mut reader = frame::Reader::new(root, &mut diary_reader)?;
-		for n in 0..32 {
-			let slot_index = SlotIndex::at(n);
-			reader.seek(slot_index)?;
-			let reading = reader.read()?;
-			assert_eq!(reading, &slots[n]);
+		let diary = Diary::load(path)?;
+		let diary_reader = diary.reader()?;
+		let reader = frame::Reader::new(root, &diary_reader)?;
+		for (n, slot) in slots.iter().enumerate() {
+			let reading = reader.slot(SlotIndex::at(n));
+			assert_eq!(&reading, slot);
 		}
 		Ok(())
 	}
@@ -88,40 +85,52 @@ mod tests {
 	#[test]
 	fn read_empty() -> Result<(), Box<dyn Error>> {
 		let diary = Diary::temp()?;
-		let mut diary_reader = diary.reader()?;
-		let reader = frame::Reader::new(Root::ZERO, &mut diary_reader)?;
-		let reading = reader.read().unwrap();
-		assert_eq!(reading, &Slot::Empty);
+		let diary_reader = diary.reader()?;
+		let reader = frame::Reader::new(Root::ZERO, &diary_reader)?;
+		let reading = reader.slot(SlotIndex::at(0));
+		assert_eq!(reading, Slot::Empty);
+		Ok(())
+	}
+
+	#[test]
+	fn frame_written_as_one_run() -> Result<(), Box<dyn Error>> {
+		let write_slot1 = WriteSlot { slot: Slot::KeyValue(1, 10), slot_index: SlotIndex::at(1) };
+		let write_slot7 = WriteSlot { slot: Slot::KeyValue(7, 70), slot_index: SlotIndex::at(7) };
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let root = frame::Writer::new(&mut diary_writer).write_root_with_slots(write_slot7, write_slot1)?;
+		assert_eq!(diary_writer.end_size(), root.pos as usize + 2 * SLOT_LEN);
+		let slots = frame::read_slots(root, &diary_writer.reader()?)?;
+		assert_eq!(slots, vec![write_slot1.slot, write_slot7.slot]);
+		// A frame reaching past the readable end fails as a whole.
+		let short_reader = diary_writer.reader()?.with_size(root.pos as usize + SLOT_LEN);
+		assert!(frame::read_slots(root, &short_reader).is_err());
 		Ok(())
 	}
 }
 
 pub(crate) struct Writer<'a> {
-	slot_writer: slot::Writer<'a>,
+	diary_writer: &'a mut diary::Writer,
 }
 
 impl<'a> Writer<'a> {
-	pub fn write_revised_root(&mut self, write_slot: WriteSlot, reader: &mut frame::Reader) -> io::Result<Root> {
-		let mut first_pos: Option<diary::Pos> = None;
+	pub fn write_revised_root(&mut self, write_slot: WriteSlot, reader: &frame::Reader) -> io::Result<Root> {
+		let mut slots = Vec::new();
 		let mut mask = 0u32;
 		for n in SlotIndex::RANGE {
 			let slot_index = SlotIndex::at(n);
 			let slot = if write_slot.slot_index == slot_index {
-				&write_slot.slot
+				write_slot.slot
 			} else {
-				reader.seek(slot_index)?;
-				reader.read()?
+				reader.slot(slot_index)
 			};
-			if slot != &Slot::Empty {
-				let (pos, _size) = self.slot_writer.write(slot)?;
+			if slot != Slot::Empty {
+				slots.push(slot);
 				mask |= slot_index.as_mask();
-				if first_pos.is_none() {
-					first_pos = Some(pos)
-				}
 			}
 		}
-		let pos = first_pos.expect("No pos for first written slot");
-		Ok(Root { pos: pos.u32(), mask })
+		assert!(!slots.is_empty(), "No slots in revised frame");
+		self.write_frame(&slots, mask)
 	}
 	pub fn write_root_with_slots(&mut self, write_slot_a: WriteSlot, write_slot_b: WriteSlot) -> io::Result<Root> {
 		debug_assert_ne!(write_slot_a.slot_index, write_slot_b.slot_index);
@@ -130,15 +139,34 @@ impl<'a> Writer<'a> {
 		} else {
 			(write_slot_b, write_slot_a)
 		};
-		let (pos, _size) = self.slot_writer.write(&first_write.slot)?;
-		self.slot_writer.write(&second_write.slot)?;
-		Ok(Root { pos: pos.u32(), mask: first_write.slot_index.as_mask() | second_write.slot_index.as_mask() })
+		let mask = first_write.slot_index.as_mask() | second_write.slot_index.as_mask();
+		self.write_frame(&[first_write.slot, second_write.slot], mask)
 	}
 	pub fn write_root_with_slot(&mut self, write_slot: WriteSlot) -> io::Result<Root> {
-		let (pos, _size) = self.slot_writer.write(&write_slot.slot)?;
-		Ok(Root { pos: pos.u32(), mask: write_slot.slot_index.as_mask() })
+		self.write_frame(&[write_slot.slot], write_slot.slot_index.as_mask())
+	}
+	/// Writes the slots of a frame, in slot-index order, as one buffer.
+	pub fn write_frame(&mut self, slots: &[Slot], mask: u32) -> io::Result<Root> {
+		debug_assert_eq!(slots.len(), mask.count_ones() as usize);
+		let (pos, _size) = self.diary_writer.write(&FrameSlots(slots))?;
+		Ok(Root { pos: pos.u32(), mask })
+	}
+	pub fn new(diary_writer: &'a mut diary::Writer) -> Self { Writer { diary_writer } }
+}
+
+/// `FrameSlots` serializes the written slots of a frame before handing them to the
+/// writer in a single write.
+struct FrameSlots<'a>(&'a [Slot]);
+
+impl<'a> WriteBytes for FrameSlots<'a> {
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		let mut bytes = Vec::with_capacity(self.0.len() * SLOT_LEN);
+		for slot in self.0 {
+			slot.write_bytes(&mut bytes)?;
+		}
+		writer.write_all(&bytes)?;
+		Ok(bytes.len())
 	}
-	pub fn new(diary_writer: &'a mut diary::Writer) -> Self { Writer { slot_writer: slot::Writer::new(diary_writer) } }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -147,41 +175,37 @@ pub(crate) struct WriteSlot {
 	pub slot_index: SlotIndex,
 }
 
-pub(crate) struct Reader<'a> {
+/// `Reader` reads all written slots of a frame when constructed and finds slots among
+/// them without further reads.
+pub(crate) struct Reader {
 	root: Root,
-	slot_reader: slot::Reader<'a>,
-	slot: Slot,
+	slots: Vec<Slot>,
 }
 
-impl<'a> Reader<'a> {
-	pub fn seek(&mut self, slot_index: SlotIndex) -> io::Result<()> {
-		let pos = self.root.slot_diary_pos(slot_index);
-		self.slot = if let Some(pos) = pos {
-			self.slot_reader.seek(pos)?;
-			match self.slot_reader.read()? {
-				None => Slot::Empty,
-				Some(slot) => *slot,
-			}
-		} else {
-			Slot::Empty
-		};
-		Ok(())
+impl Reader {
+	pub fn slot(&self, slot_index: SlotIndex) -> Slot {
+		self.root.slot_offset(slot_index).map_or(Slot::Empty, |offset| self.slots[offset])
 	}
-	pub fn read(&self) -> io::Result<&Slot> { Ok(&self.slot) }
-	pub fn new(root: Root, diary_reader: &'a mut diary::Reader) -> io::Result<Self> {
-		let slot_reader = slot::Reader::new(diary_reader);
-		Ok(Reader { root, slot_reader, slot: Slot::Empty })
+	pub fn new(root: Root, diary_reader: &diary::Reader) -> io::Result<Self> {
+		let slots = read_slots(root, diary_reader)?;
+		Ok(Reader { root, slots })
 	}
 }
 
+/// Reads the written slots of a frame in one read of `popcount(mask) * SLOT_LEN` bytes.
+pub(crate) fn read_slots(root: Root, diary_reader: &diary::Reader) -> io::Result<Vec<Slot>> {
+	let count = root.mask.count_ones() as usize;
+	let bytes = diary_reader.read_exact(diary::Pos::at(root.pos as usize), count * SLOT_LEN)?;
+	let mut bytes = &bytes[..];
+	(0..count).map(|_| Slot::read_bytes(&mut bytes)).collect()
+}
+
 impl Root {
-	pub fn slot_diary_pos(&self, slot_index: SlotIndex) -> Option<diary::Pos> {
+	/// Finds the place of the slot among the frame's written slots.
+	pub fn slot_offset(&self, slot_index: SlotIndex) -> Option<usize> {
 		let slot_mask = slot_index.as_mask();
 		if self.mask & slot_mask > 0 {
-			let predecessor_count = self.count_predecessors(slot_mask);
-			let slot_offset = predecessor_count * slot::SLOT_LEN;
-			let slot_pos = self.pos as usize + slot_offset;
-			Some(diary::Pos::at(slot_pos))
+			Some(self.count_predecessors(slot_mask))
 		} else {
 			None
 		}
@@ -192,4 +216,4 @@ impl Root {
 		let predecessor_map = self.mask & predecessor_mask;
 		predecessor_map.count_ones() as usize
 	}
-}
\ No newline at end of file
+}
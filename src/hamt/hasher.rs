@@ -3,6 +3,7 @@ use std::hash::Hasher;
 use crate::util;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
 	use crate::hamt::hasher::universal;
 
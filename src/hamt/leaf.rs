@@ -0,0 +1,99 @@
+use std::io;
+use std::io::{Read, Write};
+
+use crate::bytes::{ReadBytes, WriteBytes};
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::bytes::{ReadBytes, WriteBytes};
+	use crate::hamt::leaf::Leaf;
+
+	#[test]
+	fn write_read() {
+		let leaf = Leaf::single(&[1, 2], 10).with_value(&[3], 30);
+		let mut cursor = Cursor::new(Vec::new());
+		leaf.write_bytes(&mut cursor).unwrap();
+		cursor.set_position(0);
+		let leaf_final = Leaf::read_bytes(&mut cursor).unwrap();
+		assert_eq!(leaf_final, leaf);
+	}
+
+	#[test]
+	fn values_by_full_key() {
+		let leaf = Leaf::single(&[1], 10)
+			.with_value(&[2], 20)
+			.with_value(&[1], 11);
+		assert_eq!(leaf.value(&[1]), Some(11));
+		assert_eq!(leaf.value(&[2]), Some(20));
+		assert_eq!(leaf.without_key(&[1]).value(&[1]), None);
+	}
+}
+
+/// `Leaf` holds the full serialized keys and the value positions of the keys found at a
+/// `Slot::KeyValue`. A leaf holds more than one entry only when the keys' hashes collide.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Leaf {
+	pub entries: Vec<LeafEntry>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct LeafEntry {
+	pub key: Vec<u8>,
+	pub value: u32,
+}
+
+impl Leaf {
+	pub fn value(&self, key: &[u8]) -> Option<u32> {
+		self.entries.iter()
+			.find(|entry| entry.key == key)
+			.map(|entry| entry.value)
+	}
+	pub fn with_value(&self, key: &[u8], value: u32) -> Leaf {
+		let mut entries = self.entries.clone();
+		match entries.iter_mut().find(|entry| entry.key == key) {
+			Some(entry) => entry.value = value,
+			None => entries.push(LeafEntry { key: key.to_vec(), value }),
+		}
+		Leaf { entries }
+	}
+	pub fn without_key(&self, key: &[u8]) -> Leaf {
+		let entries = self.entries.iter()
+			.filter(|entry| entry.key != key)
+			.cloned()
+			.collect();
+		Leaf { entries }
+	}
+	pub fn single(key: &[u8], value: u32) -> Leaf {
+		Leaf { entries: vec![LeafEntry { key: key.to_vec(), value }] }
+	}
+}
+
+impl WriteBytes for Leaf {
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
+		let mut size = (self.entries.len() as u16).write_bytes(writer)?;
+		for entry in &self.entries {
+			size += (entry.key.len() as u32).write_bytes(writer)?;
+			writer.write_all(&entry.key)?;
+			size += entry.key.len();
+			size += entry.value.write_bytes(writer)?;
+		}
+		Ok(size)
+	}
+}
+
+impl ReadBytes<Leaf> for Leaf {
+	fn read_bytes(reader: &mut impl Read) -> io::Result<Self> {
+		let count = u16::read_bytes(reader)?;
+		let mut entries = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			let key_len = u32::read_bytes(reader)?;
+			let mut key = vec![0u8; key_len as usize];
+			reader.read_exact(&mut key)?;
+			let value = u32::read_bytes(reader)?;
+			entries.push(LeafEntry { key, value });
+		}
+		Ok(Leaf { entries })
+	}
+}
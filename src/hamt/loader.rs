@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::diary;
+use crate::hamt::{frame, Key, Reader, Root};
+use crate::hamt::frame::SlotIndex;
+use crate::hamt::leaf::Leaf;
+use crate::hamt::slot::Slot;
+use crate::hamt::slot_indexer::{SlotIndexer, UniversalSlotPicker};
+use crate::hamt::writer::require_empty_high_bit_in_position;
+
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod tests {
+	use std::error::Error;
+
+	use crate::{diary, ObjectId};
+	use crate::bytes::WriteBytes;
+	use crate::diary::Diary;
+	use crate::hamt::data::fixture::ZeroThenKeySlotIndexer;
+	use crate::hamt::Key;
+	use crate::hamt::leaf::Leaf;
+	use crate::hamt::loader::{load, load_with};
+	use crate::hamt::reader::Reader;
+	use crate::hamt::Root;
+	use crate::hamt::slot::{Slot, SLOT_LEN};
+	use crate::hamt::slot_indexer::UniversalSlotPicker;
+	use crate::hamt::writer::Writer;
+
+	#[test]
+	fn load_matches_incremental_writes() -> Result<(), Box<dyn Error>> {
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let indexer = |key: u32| ZeroThenKeySlotIndexer { key, transition_depth: 2 };
+		let incremental_root = {
+			let mut writer = Writer::new(Root::ZERO, &mut diary_writer);
+			for key in 1u32..30 {
+				writer.write(&[key as u8], key * 10, &mut indexer(key))?;
+			}
+			writer.write(&[5], 55, &mut indexer(5))?
+		};
+		// The existing tree holds the first keys, and the load adds the rest.
+		let existing_root = {
+			let mut writer = Writer::new(Root::ZERO, &mut diary_writer);
+			for key in 1u32..6 {
+				writer.write(&[key as u8], key * 10, &mut indexer(key))?;
+			}
+			writer.write(&[5], 1, &mut indexer(5))?
+		};
+		let entries = (6u32..30).map(|key| (vec![key as u8], key * 10, indexer(key)))
+			.chain(vec![(vec![5], 2, indexer(5)), (vec![5], 55, indexer(5))]);
+		let loaded_root = load_with(existing_root, entries, &mut diary_writer)?;
+		let mut diary_reader = diary_writer.reader()?;
+		for key in 1u32..30 {
+			let incremental = Reader::new(incremental_root).read(&[key as u8], &mut indexer(key), &mut diary_reader)?;
+			let loaded = Reader::new(loaded_root).read(&[key as u8], &mut indexer(key), &mut diary_reader)?;
+			assert_eq!(loaded, incremental);
+		}
+		assert_eq!(loaded_root.mask, incremental_root.mask);
+		Ok(())
+	}
+
+	#[test]
+	fn load_writes_only_live_frames_and_leaves() -> Result<(), Box<dyn Error>> {
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let keys = (0..1000u32).map(|n| ObjectId::new(n.to_string())).collect::<Vec<_>>();
+		let entries = keys.iter().enumerate().map(|(n, key)| (key.clone(), diary::Pos::at(n)));
+		let root = load(Root::ZERO, entries, &mut diary_writer)?;
+		let mut diary_reader = diary_writer.reader()?;
+		let mut live_len = 0;
+		let mut roots = vec![root];
+		while let Some(root) = roots.pop() {
+			for slot in Reader::new(root).read_frame(root, &mut diary_reader)?.iter() {
+				live_len += SLOT_LEN;
+				match *slot {
+					Slot::Root(sub_root) => roots.push(sub_root),
+					Slot::KeyValue(_, leaf_pos) => {
+						let leaf = diary_reader.read::<Leaf>(diary::Pos::at(leaf_pos as usize))?;
+						live_len += leaf.write_bytes(&mut Vec::new())?;
+					}
+					Slot::Empty => (),
+				}
+			}
+		}
+		assert_eq!(live_len, diary_writer.end_size());
+		for (n, key) in keys.iter().enumerate() {
+			let mut slot_indexer = UniversalSlotPicker::new(key.universal(1));
+			assert_eq!(Reader::new(root).read(&key.key_bytes(), &mut slot_indexer, &mut diary_reader)?, Some(n as u32));
+		}
+		Ok(())
+	}
+}
+
+/// Stores the value positions at their keys in the tree at the root and returns the new
+/// root. Keys are partitioned by slot index and the tree is built bottom-up, so each
+/// changed leaf and frame is written once. A later position for a key replaces an
+/// earlier one, as it would with incremental writes.
+pub(crate) fn load<K: Key>(root: Root, entries: impl IntoIterator<Item=(K, diary::Pos)>, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+	let entries = entries.into_iter().map(|(key, pos)| {
+		(key.key_bytes(), pos.u32(), UniversalSlotPicker::new(key.universal(1)))
+	});
+	load_with(root, entries, diary_writer)
+}
+
+/// Stores value positions in the inner trees of a map of maps, where each value of the
+/// outer tree at the root is the root of an inner tree. Every inner tree is loaded before
+/// the outer tree, and only the inner trees whose roots changed are written to it. The
+/// outer tree's new root is returned.
+pub(crate) fn load_nested<K: Key + Clone, L: Key>(root: Root, entries: impl IntoIterator<Item=(K, L, diary::Pos)>, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+	let mut inners: Vec<(K, Vec<(L, diary::Pos)>)> = Vec::new();
+	let mut inner_indices = HashMap::new();
+	for (outer_key, inner_key, pos) in entries {
+		let index = *inner_indices.entry(outer_key.key_bytes()).or_insert_with(|| {
+			inners.push((outer_key.clone(), Vec::new()));
+			inners.len() - 1
+		});
+		inners[index].1.push((inner_key, pos));
+	}
+	let mut diary_reader = diary_writer.reader()?;
+	let mut outer_entries = Vec::new();
+	for (outer_key, inner_entries) in inners {
+		let old_inner_root = Reader::new(root).read_value::<Root>(&outer_key, &mut diary_reader)?.unwrap_or(Root::ZERO);
+		let inner_root = load(old_inner_root, inner_entries, diary_writer)?;
+		if inner_root != old_inner_root {
+			let (pos, _size) = diary_writer.write(&inner_root)?;
+			outer_entries.push((outer_key, pos));
+		}
+	}
+	load(root, outer_entries, diary_writer)
+}
+
+pub(crate) fn load_with<S: SlotIndexer + 'static>(root: Root, entries: impl IntoIterator<Item=(Vec<u8>, u32, S)>, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+	let mut buckets: Vec<Bucket> = Vec::new();
+	let mut bucket_indices = HashMap::new();
+	for (key, value, slot_indexer) in entries {
+		let index = *bucket_indices.entry(slot_indexer.key()).or_insert_with(|| {
+			buckets.push(Bucket { hash: slot_indexer.key(), slot_indexer: Box::new(slot_indexer), stored: None, pairs: Vec::new() });
+			buckets.len() - 1
+		});
+		buckets[index].pairs.push((key, value));
+	}
+	if buckets.is_empty() {
+		return Ok(root);
+	}
+	let mut diary_reader = diary_writer.reader()?;
+	load_frame(root, buckets, 0, &mut diary_reader, diary_writer)
+}
+
+/// `Bucket` gathers the pairs for keys sharing a hash, to be stored on top of the leaf
+/// already stored for the hash, if any.
+struct Bucket {
+	hash: u32,
+	slot_indexer: Box<dyn SlotIndexer>,
+	stored: Option<(u32, Leaf)>,
+	pairs: Vec<(Vec<u8>, u32)>,
+}
+
+impl Bucket {
+	fn write(self, diary_writer: &mut diary::Writer) -> io::Result<Slot> {
+		let leaf = match self.stored {
+			Some((leaf_pos, _)) if self.pairs.is_empty() => return Ok(Slot::KeyValue(self.hash, leaf_pos)),
+			Some((_, leaf)) => Some(leaf),
+			None => None,
+		};
+		let leaf = self.pairs.into_iter().fold(leaf, |leaf, (key, value)| Some(match leaf {
+			Some(leaf) => leaf.with_value(&key, value),
+			None => Leaf::single(&key, value),
+		})).expect("Bucket holds a leaf or pairs");
+		let (leaf_pos, _size) = diary_writer.write(&leaf)?;
+		Ok(Slot::KeyValue(self.hash, leaf_pos.u32()))
+	}
+}
+
+/// Partitions the buckets by their slot indices at the depth, builds each slot from its
+/// partition and the frame's stored slot, and writes the frame once.
+fn load_frame(root: Root, buckets: Vec<Bucket>, depth: usize, diary_reader: &mut diary::Reader, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+	let stored_slots = match root.mask {
+		0 => Vec::new().into(),
+		_ => Reader::new(root).read_frame(root, diary_reader)?,
+	};
+	let mut partitions = SlotIndex::RANGE.map(|_| Vec::new()).collect::<Vec<_>>();
+	for mut bucket in buckets {
+		let n = bucket.slot_indexer.slot_index(depth) as usize;
+		partitions[n].push(bucket);
+	}
+	let mut slots = Vec::new();
+	let mut mask = 0u32;
+	for (n, partition) in partitions.into_iter().enumerate() {
+		let slot_index = SlotIndex::at(n);
+		let stored_slot = root.slot_offset(slot_index).map_or(Slot::Empty, |offset| stored_slots[offset]);
+		let slot = match partition.is_empty() {
+			true => stored_slot,
+			false => load_slot(stored_slot, partition, depth, diary_reader, diary_writer)?,
+		};
+		if slot != Slot::Empty {
+			slots.push(slot);
+			mask |= slot_index.as_mask();
+		}
+	}
+	let root = frame::Writer::new(diary_writer).write_frame(&slots, mask)?;
+	require_empty_high_bit_in_position(root)
+}
+
+/// Builds a slot from its stored slot and a non-empty partition of buckets. A stored
+/// leaf joins the partition as the base of its hash's bucket.
+fn load_slot(stored_slot: Slot, mut partition: Vec<Bucket>, depth: usize, diary_reader: &mut diary::Reader, diary_writer: &mut diary::Writer) -> io::Result<Slot> {
+	match stored_slot {
+		Slot::Root(sub_root) => Ok(Slot::Root(load_frame(sub_root, partition, depth + 1, diary_reader, diary_writer)?)),
+		Slot::KeyValue(hash, leaf_pos) => {
+			let stored = Some((leaf_pos, diary_reader.read_cached::<Leaf>(diary::Pos::at(leaf_pos as usize))?));
+			match partition.iter_mut().find(|bucket| bucket.hash == hash) {
+				Some(bucket) => bucket.stored = stored,
+				None => {
+					let slot_indexer = partition[0].slot_indexer.with_key(hash);
+					partition.push(Bucket { hash, slot_indexer, stored, pairs: Vec::new() });
+				}
+			}
+			load_slot(Slot::Empty, partition, depth, diary_reader, diary_writer)
+		}
+		Slot::Empty if partition.len() == 1 => partition.pop().expect("Single bucket").write(diary_writer),
+		Slot::Empty => Ok(Slot::Root(load_frame(Root::ZERO, partition, depth + 1, diary_reader, diary_writer)?)),
+	}
+}
@@ -5,21 +5,26 @@ pub use root::*;
 
 use crate::{diary, hamt};
 use crate::bytes::{ReadBytes, WriteBytes};
-use crate::hamt::frame::SlotIndex;
 use crate::hamt::hasher::UniversalHasher;
+use crate::hamt::leaf::Leaf;
 use crate::hamt::slot::Slot;
 use crate::hamt::slot_indexer::UniversalSlotPicker;
 use crate::hamt::writer::Writer;
 
 pub(crate) use self::reader::Reader;
+pub(crate) use self::loader::load_nested;
+pub use self::transient::{NestedTransient, Transient};
 
 pub(crate) mod frame;
 mod root;
 mod data;
 mod hasher;
-mod slot;
+mod leaf;
+mod loader;
+pub(crate) mod slot;
 mod reader;
 mod slot_indexer;
+mod transient;
 mod writer;
 
 pub struct Hamt {
@@ -28,49 +33,75 @@ pub struct Hamt {
 
 impl Hamt {
 	pub fn write_value(&mut self, key: &impl hamt::Key, value: &impl WriteBytes, diary_writer: &mut diary::Writer) -> io::Result<()> {
-		let key = key.universal(1);
-		let mut slot_indexer = UniversalSlotPicker::new(key);
 		let (pos, _size) = diary_writer.write(value)?;
+		self.write_value_pos(key, pos, diary_writer)
+	}
+	/// Stores a value already written to the diary at the given position.
+	pub fn write_value_pos(&mut self, key: &impl hamt::Key, pos: diary::Pos, diary_writer: &mut diary::Writer) -> io::Result<()> {
+		let mut slot_indexer = UniversalSlotPicker::new(key.universal(1));
 		let mut writer = Writer::new(self.root, diary_writer);
-		self.root = writer.write(pos.u32(), &mut slot_indexer)?;
+		self.root = writer.write(&key.key_bytes(), pos.u32(), &mut slot_indexer)?;
 		Ok(())
 	}
+	pub fn remove_value(&mut self, key: &impl hamt::Key, diary_writer: &mut diary::Writer) -> io::Result<()> {
+		let mut slot_indexer = UniversalSlotPicker::new(key.universal(1));
+		let mut writer = Writer::new(self.root, diary_writer);
+		self.root = writer.remove(&key.key_bytes(), &mut slot_indexer)?;
+		Ok(())
+	}
+	/// Stores the values at their keys, writing each changed frame of the tree once.
+	/// Loading many values this way costs far less than writing them one at a time.
+	pub fn load_values<K: hamt::Key, V: WriteBytes>(&mut self, pairs: impl IntoIterator<Item=(K, V)>, diary_writer: &mut diary::Writer) -> io::Result<()> {
+		let mut entries = Vec::new();
+		for (key, value) in pairs {
+			let (pos, _size) = diary_writer.write(&value)?;
+			entries.push((key, pos));
+		}
+		self.load_value_positions(entries, diary_writer)
+	}
+	/// Stores values already written to the diary at the given positions.
+	pub fn load_value_positions<K: hamt::Key>(&mut self, entries: impl IntoIterator<Item=(K, diary::Pos)>, diary_writer: &mut diary::Writer) -> io::Result<()> {
+		self.root = loader::load(self.root, entries, diary_writer)?;
+		Ok(())
+	}
+	pub fn is_empty(&self) -> bool { self.root.mask == 0 }
 	pub fn reader(&self) -> io::Result<Reader> { Ok(Reader::new(self.root)) }
 	pub fn new(root: Root) -> Self { Hamt { root } }
 }
 
 impl Reader {
-	pub fn read_all<V: ReadBytes<V>>(&self, diary_reader: &mut diary::Reader) -> io::Result<Vec<V>> {
+	pub fn read_all<V: ReadBytes<V> + Clone + Send + Sync + 'static>(&self, diary_reader: &mut diary::Reader) -> io::Result<Vec<V>> {
 		let mut positions = Vec::new();
 		{
 			let mut roots = vec![self.root];
 			loop {
 				match roots.pop() {
 					None => break,
-					Some(root) => for n in SlotIndex::RANGE {
-						match self.read_slot(root, SlotIndex::at(n), diary_reader)? {
+					Some(root) => for slot in self.read_frame(root, diary_reader)?.iter() {
+						match *slot {
 							Slot::Empty => (),
-							Slot::KeyValue(_, value) => positions.push(value),
+							Slot::KeyValue(_, leaf_pos) => {
+								let leaf = diary_reader.read_cached::<Leaf>(diary::Pos::at(leaf_pos as usize))?;
+								positions.extend(leaf.entries.into_iter().map(|entry| entry.value));
+							}
 							Slot::Root(root) => roots.push(root),
 						}
 					},
 				}
 			}
 		}
-		let values = positions.into_iter()
-			.map(|it| diary_reader.read::<V>(diary::Pos::at(it as usize)))
-			.collect();
-		values
+		positions.into_iter()
+			.map(|it| diary_reader.read_cached::<V>(diary::Pos::at(it as usize)))
+			.collect()
 	}
 
-	pub fn read_value<V: ReadBytes<V>>(&self, key: &impl hamt::Key, diary_reader: &mut diary::Reader) -> io::Result<Option<V>> {
-		let key = key.universal(1);
-		let mut slot_indexer = UniversalSlotPicker::new(key);
-		let value = match self.read(&mut slot_indexer, diary_reader)? {
+	pub fn read_value<V: ReadBytes<V> + Clone + Send + Sync + 'static>(&self, key: &impl hamt::Key, diary_reader: &mut diary::Reader) -> io::Result<Option<V>> {
+		let mut slot_indexer = UniversalSlotPicker::new(key.universal(1));
+		let value = match self.read(&key.key_bytes(), &mut slot_indexer, diary_reader)? {
 			None => None,
 			Some(pos) => {
 				let pos = diary::Pos::at(pos as usize);
-				let value = diary_reader.read::<V>(pos)?;
+				let value = diary_reader.read_cached::<V>(pos)?;
 				Some(value)
 			}
 		};
@@ -78,10 +109,17 @@ impl Reader {
 	}
 }
 
-pub trait Key: Hash {
+/// Keys are found by their 31-bit universal hash and told apart by their serialized bytes,
+/// so equal keys must hash and serialize identically.
+pub trait Key: Hash + WriteBytes {
 	fn universal(&self, level: u64) -> u32 {
 		let mut hasher = UniversalHasher::new(level);
 		self.hash(&mut hasher);
 		(hasher.finish() as u32) & 0x7fffffff
 	}
+	fn key_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		self.write_bytes(&mut bytes).expect("Key bytes write to memory");
+		bytes
+	}
 }
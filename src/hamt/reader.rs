@@ -1,8 +1,10 @@
 use std::io;
+use std::sync::Arc;
 
 use crate::diary;
 use crate::hamt::{frame, Root};
 use crate::hamt::frame::SlotIndex;
+use crate::hamt::leaf::Leaf;
 use crate::hamt::slot::Slot;
 use crate::hamt::slot_indexer::SlotIndexer;
 
@@ -20,7 +22,7 @@ mod tests {
 		let reader = Reader::new(Root::ZERO);
 		for key in 1u32..4 {
 			let mut slot_indexer = ZeroThenKeySlotIndexer { key, transition_depth: 0 };
-			let value = reader.read(&mut slot_indexer, &mut diary_reader).unwrap();
+			let value = reader.read(&[key as u8], &mut slot_indexer, &mut diary_reader).unwrap();
 			assert_eq!(value, None)
 		}
 	}
@@ -32,7 +34,9 @@ pub struct Reader {
 }
 
 impl Reader {
-	pub fn read(&self, slot_indexer: &mut impl SlotIndexer, diary_reader: &mut diary::Reader) -> io::Result<Option<u32>> {
+	/// Finds the position of the value stored for the full key. The slot indexer supplies
+	/// the key's hash.
+	pub fn read(&self, key: &[u8], slot_indexer: &mut impl SlotIndexer, diary_reader: &mut diary::Reader) -> io::Result<Option<u32>> {
 		let mut root = self.root;
 		let mut depth = 0;
 		let mut leaf_value = None;
@@ -44,9 +48,10 @@ impl Reader {
 					root = sub_root;
 					depth += 1;
 				}
-				Slot::KeyValue(key, value) => {
-					if key == slot_indexer.key() {
-						leaf_value = Some(value);
+				Slot::KeyValue(hash, leaf_pos) => {
+					if hash == slot_indexer.key() {
+						let leaf = diary_reader.read_cached::<Leaf>(diary::Pos::at(leaf_pos as usize))?;
+						leaf_value = leaf.value(key);
 					} else {
 						leaf_value = None;
 					}
@@ -61,11 +66,16 @@ impl Reader {
 		Ok(leaf_value)
 	}
 	pub fn read_slot(&self, root: Root, slot_index: SlotIndex, diary_reader: &mut diary::Reader) -> io::Result<Slot> {
+		let frame = self.read_frame(root, diary_reader)?;
+		Ok(root.slot_offset(slot_index).map_or(Slot::Empty, |offset| frame[offset]))
+	}
+	/// Reads the written slots of the frame at a root, through the reader's cache if it has one.
+	pub fn read_frame(&self, root: Root, diary_reader: &mut diary::Reader) -> io::Result<Arc<[Slot]>> {
 		debug_assert!(root.pos <= self.root.pos);
-		let mut frame_reader = frame::Reader::new(root, diary_reader)?;
-		frame_reader.seek(slot_index)?;
-		let slot = frame_reader.read()?;
-		Ok(*slot)
+		match diary_reader.cache().cloned() {
+			Some(cache) => cache.frame(root.pos as usize, || frame::read_slots(root, diary_reader)),
+			None => Ok(frame::read_slots(root, diary_reader)?.into()),
+		}
 	}
 	pub fn new(root: Root) -> Self {
 		Reader { root }
@@ -5,10 +5,6 @@ use crate::bytes::{ReadBytes, WriteBytes};
 use crate::hamt::slot::Slot;
 pub(crate) use crate::hamt::slot::SLOT_LEN as ROOT_LEN;
 
-pub(crate) use self::prod_ab::*;
-
-mod prod_ab;
-
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Root {
 	pub pos: u32,
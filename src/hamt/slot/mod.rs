@@ -5,21 +5,20 @@ use crate::bytes::{ReadBytes, WriteBytes};
 use crate::hamt::root::Root;
 use crate::util::{clr_high_bit, is_high_bit_set, set_high_bit, U32x2};
 
-pub(crate) use self::read_write::{Reader, SLOT_LEN, Writer};
+pub(crate) use self::read_write::SLOT_LEN;
 
 pub mod read_write;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// A `Slot::KeyValue` holds a key hash and the position of the `Leaf` holding the full
+/// keys and value positions for that hash.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub enum Slot {
+	#[default]
 	Empty,
 	KeyValue(u32, u32),
 	Root(Root),
 }
 
-impl Default for Slot {
-	fn default() -> Self { Slot::Empty }
-}
-
 impl WriteBytes for Slot {
 	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> {
 		let bytes = match self {
@@ -1,12 +1,8 @@
-use std::io;
-
-use crate::diary;
-use crate::hamt::slot::Slot;
-
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
 	use crate::diary::Diary;
-	use crate::hamt::{Root, slot};
+	use crate::hamt::Root;
 	use crate::hamt::slot::read_write::SLOT_LEN;
 	use crate::hamt::slot::Slot;
 
@@ -30,46 +26,15 @@ mod tests {
 		let (file_path, pos) = {
 			let diary = Diary::temp().unwrap();
 			let mut diary_writer = diary.writer().unwrap();
-			let mut writer = slot::Writer { diary_writer: &mut diary_writer };
-			let (pos, size) = writer.write(&slot).unwrap();
+			let (pos, size) = diary_writer.write(&slot).unwrap();
 			assert_eq!(size, SLOT_LEN);
 			(diary.file_path, pos)
 		};
 		let diary = Diary::load(&file_path).unwrap();
-		let mut diary_reader = diary.reader().unwrap();
-		let mut reader = slot::Reader::new(&mut diary_reader);
-		reader.seek(pos).unwrap();
-		let reading = reader.read().unwrap();
-		assert_eq!(reading, &Some(slot))
-	}
-}
-
-pub(crate) struct Reader<'a> {
-	diary_reader: &'a mut diary::Reader,
-	value: Option<Slot>,
-}
-
-impl<'a> Reader<'a> {
-	pub fn seek(&mut self, pos: diary::Pos) -> io::Result<diary::Pos> {
-		let value = self.diary_reader.read(pos)?;
-		self.value = Some(value);
-		Ok(pos)
-	}
-	pub fn read(&self) -> io::Result<&Option<Slot>> { Ok(&self.value) }
-	pub fn new(diary_reader: &'a mut diary::Reader) -> Self {
-		Reader { diary_reader, value: None }
-	}
-}
-
-pub(crate) struct Writer<'a> {
-	diary_writer: &'a mut diary::Writer
-}
-
-impl<'a> Writer<'a> {
-	pub fn write(&mut self, slot: &Slot) -> io::Result<(diary::Pos, usize)> {
-		self.diary_writer.write(slot)
+		let diary_reader = diary.reader().unwrap();
+		let reading = diary_reader.read::<Slot>(pos).unwrap();
+		assert_eq!(reading, slot)
 	}
-	pub fn new(diary_writer: &'a mut diary::Writer) -> Self { Writer { diary_writer } }
 }
 
 pub(crate) static SLOT_LEN: usize = 8;
@@ -3,6 +3,7 @@ use std::ops::{Deref, DerefMut};
 use crate::hamt::hasher::universal;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
 	use std::collections::HashSet;
 
@@ -51,8 +52,7 @@ impl SlotIndexer for UniversalSlotPicker {
 		self.prepare_hashes(hashes_index);
 		let hash = &self.hashes[hashes_index];
 		let shift = (depth % LEVELS_PER_HASH) * BITS_PER_LEVEL;
-		let slot_index = ((hash >> shift) & LEVEL_MASK) as u8;
-		slot_index
+		((hash >> shift) & LEVEL_MASK) as u8
 	}
 
 	fn with_key(&self, key: u32) -> Box<dyn SlotIndexer> {
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::diary;
+use crate::hamt::{frame, Key, Reader, Root};
+use crate::hamt::frame::SlotIndex;
+use crate::hamt::leaf::Leaf;
+use crate::hamt::slot::Slot;
+use crate::hamt::slot_indexer::{SlotIndexer, UniversalSlotPicker};
+use crate::hamt::writer::require_empty_high_bit_in_position;
+
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod tests {
+	use std::error::Error;
+
+	use crate::diary::Diary;
+	use crate::ObjectId;
+	use crate::hamt::data::fixture::ZeroThenKeySlotIndexer;
+	use crate::hamt::reader::Reader;
+	use crate::hamt::Root;
+	use crate::hamt::slot_indexer::SlotIndexer;
+	use crate::hamt::transient::{NestedTransient, Transient};
+	use crate::hamt::writer::Writer;
+
+	#[test]
+	fn commit_matches_persistent_writes() -> Result<(), Box<dyn Error>> {
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let mut indexers = (1u32..6).map(|key| ZeroThenKeySlotIndexer { key, transition_depth: 2 }).collect::<Vec<_>>();
+		let persistent_root = {
+			let mut writer = Writer::new(Root::ZERO, &mut diary_writer);
+			for indexer in indexers.iter_mut() {
+				writer.write(&[indexer.key() as u8], indexer.key() * 10, indexer)?;
+			}
+			writer.remove(&[3], &mut indexers[2])?
+		};
+		let mut diary_reader = diary_writer.reader()?;
+		let mut transient = Transient::new(Root::ZERO, &mut diary_reader)?;
+		for indexer in indexers.iter_mut() {
+			transient.write(&[indexer.key() as u8], 1, indexer, &mut diary_reader)?;
+			transient.write(&[indexer.key() as u8], indexer.key() * 10, indexer, &mut diary_reader)?;
+		}
+		transient.remove(&[3], &mut indexers[2], &mut diary_reader)?;
+		let transient_root = transient.commit(&mut diary_writer)?;
+		let mut diary_reader = diary_writer.reader()?;
+		for indexer in indexers.iter_mut() {
+			let key = [indexer.key() as u8];
+			let persistent = Reader::new(persistent_root).read(&key, indexer, &mut diary_reader)?;
+			let transient = Reader::new(transient_root).read(&key, indexer, &mut diary_reader)?;
+			assert_eq!(persistent, transient);
+		}
+		assert_eq!(Reader::new(transient_root).read(&[3], &mut indexers[2], &mut diary_reader)?, None);
+		assert_eq!(transient_root.mask, persistent_root.mask);
+		Ok(())
+	}
+
+	#[test]
+	fn unchanged_commit_writes_nothing() -> Result<(), Box<dyn Error>> {
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let mut indexer = ZeroThenKeySlotIndexer { key: 1, transition_depth: 1 };
+		let root = Writer::new(Root::ZERO, &mut diary_writer).write(&[1], 10, &mut indexer)?;
+		let end_size = diary_writer.end_size();
+		let mut diary_reader = diary_writer.reader()?;
+		let mut transient = Transient::new(root, &mut diary_reader)?;
+		transient.remove(&[2], &mut ZeroThenKeySlotIndexer { key: 2, transition_depth: 1 }, &mut diary_reader)?;
+		assert_eq!(transient.commit(&mut diary_writer)?, root);
+		assert_eq!(diary_writer.end_size(), end_size);
+		Ok(())
+	}
+
+	#[test]
+	fn nested_commit_writes_outer_path_once() -> Result<(), Box<dyn Error>> {
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let mut diary_reader = diary_writer.reader()?;
+		let outer_key = ObjectId::new("outer");
+		let mut nested = NestedTransient::new(Root::ZERO, &mut diary_reader)?;
+		for n in 0..100u32 {
+			let (pos, _) = diary_writer.write(&n)?;
+			nested.write_value_pos(&outer_key, &ObjectId::new(n.to_string()), Some(pos), &mut diary_reader)?;
+		}
+		nested.write_value_pos(&outer_key, &ObjectId::new("7"), None, &mut diary_reader)?;
+		let values_end = diary_writer.end_size();
+		let root = nested.commit(&mut diary_writer)?;
+		let mut diary_reader = diary_writer.reader()?;
+		let inner_root = Reader::new(root).read_value::<Root>(&outer_key, &mut diary_reader)?.unwrap();
+		let mut values = Reader::new(inner_root).read_all::<u32>(&mut diary_reader)?;
+		values.sort();
+		assert_eq!(values, (0..100).filter(|n| *n != 7).collect::<Vec<_>>());
+		// The outer tree is written once, after the values, as a leaf and a one-slot frame.
+		assert!(root.pos as usize > values_end);
+		assert_eq!(diary_writer.end_size() - root.pos as usize, 8);
+		Ok(())
+	}
+}
+
+/// `Transient` applies writes and removals to in-memory copies of the frames and leaves
+/// they touch, reading the persistent tree as needed. Committing writes each changed
+/// leaf and frame once, bottom-up, and leaves untouched sub-trees where they are.
+pub struct Transient {
+	frame: Frame,
+}
+
+impl Transient {
+	/// Stores the position of a value already written to the diary.
+	pub fn write_value_pos(&mut self, key: &impl Key, pos: diary::Pos, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		let mut slot_indexer = UniversalSlotPicker::new(key.universal(1));
+		self.write(&key.key_bytes(), pos.u32(), &mut slot_indexer, diary_reader)
+	}
+	pub fn remove_value(&mut self, key: &impl Key, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		let mut slot_indexer = UniversalSlotPicker::new(key.universal(1));
+		self.remove(&key.key_bytes(), &mut slot_indexer, diary_reader)
+	}
+	pub(crate) fn write(&mut self, key: &[u8], value: u32, slot_indexer: &mut impl SlotIndexer, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		self.frame.write(key, value, slot_indexer, 0, diary_reader)
+	}
+	pub(crate) fn remove(&mut self, key: &[u8], slot_indexer: &mut impl SlotIndexer, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		self.frame.remove(key, slot_indexer, 0, diary_reader).map(|_| ())
+	}
+	/// Writes the changed leaves and frames and returns the new root. An empty tree
+	/// becomes `Root::ZERO`, and an unchanged tree keeps its root.
+	pub fn commit(self, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+		self.frame.commit(diary_writer)
+	}
+	pub fn new(root: Root, diary_reader: &mut diary::Reader) -> io::Result<Self> {
+		Ok(Transient { frame: Frame::load(root, diary_reader)? })
+	}
+}
+
+/// `NestedTransient` batches changes to a map of maps, where each value of the outer
+/// tree is the root of an inner tree. Inner trees are committed before the outer tree,
+/// so the outer tree is revised once per commit rather than once per change.
+pub struct NestedTransient<K> {
+	root: Root,
+	outer: Transient,
+	inners: Vec<(K, Root, Transient)>,
+	inner_indices: HashMap<Vec<u8>, usize>,
+}
+
+impl<K: Key + Clone> NestedTransient<K> {
+	/// Stores the position of a value in the inner tree at the outer key, or removes the
+	/// inner key when there is no position.
+	pub fn write_value_pos(&mut self, outer_key: &K, inner_key: &impl Key, pos: Option<diary::Pos>, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		let inner = self.inner(outer_key, diary_reader)?;
+		match pos {
+			None => inner.remove_value(inner_key, diary_reader),
+			Some(pos) => inner.write_value_pos(inner_key, pos, diary_reader),
+		}
+	}
+	fn inner(&mut self, outer_key: &K, diary_reader: &mut diary::Reader) -> io::Result<&mut Transient> {
+		let key_bytes = outer_key.key_bytes();
+		let index = match self.inner_indices.get(&key_bytes) {
+			Some(index) => *index,
+			None => {
+				let root = Reader::new(self.root).read_value::<Root>(outer_key, diary_reader)?.unwrap_or(Root::ZERO);
+				self.inners.push((outer_key.clone(), root, Transient::new(root, diary_reader)?));
+				self.inner_indices.insert(key_bytes, self.inners.len() - 1);
+				self.inners.len() - 1
+			}
+		};
+		Ok(&mut self.inners[index].2)
+	}
+	/// Commits the inner trees, stores their new roots in the outer tree, drops emptied
+	/// inner trees from it, and returns the outer tree's new root.
+	pub fn commit(self, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+		let mut diary_reader = diary_writer.reader()?;
+		let mut outer = self.outer;
+		for (key, root, inner) in self.inners {
+			let inner_root = inner.commit(diary_writer)?;
+			if inner_root == root {
+				continue;
+			}
+			if inner_root.mask == 0 {
+				outer.remove_value(&key, &mut diary_reader)?;
+			} else {
+				let (pos, _size) = diary_writer.write(&inner_root)?;
+				outer.write_value_pos(&key, pos, &mut diary_reader)?;
+			}
+		}
+		outer.commit(diary_writer)
+	}
+	pub fn new(root: Root, diary_reader: &mut diary::Reader) -> io::Result<Self> {
+		let outer = Transient::new(root, diary_reader)?;
+		Ok(NestedTransient { root, outer, inners: Vec::new(), inner_indices: HashMap::new() })
+	}
+}
+
+/// A frame read into memory. `origin` is the root of the persistent frame it copies.
+struct Frame {
+	origin: Root,
+	changed: bool,
+	entries: Vec<Entry>,
+}
+
+enum Entry {
+	/// A slot of the persistent tree not yet read into memory.
+	Stored(Slot),
+	/// A leaf changed in memory, with the key hash its keys share.
+	Leaf(u32, Leaf),
+	/// A sub-frame read into memory.
+	Frame(Box<Frame>),
+}
+
+impl Entry {
+	fn is_leaf(&self) -> bool {
+		matches!(self, Entry::Stored(Slot::KeyValue(_, _)) | Entry::Leaf(_, _))
+	}
+	fn hash(&self) -> Option<u32> {
+		match self {
+			Entry::Stored(Slot::KeyValue(hash, _)) | Entry::Leaf(hash, _) => Some(*hash),
+			_ => None,
+		}
+	}
+	/// Reads a stored sub-frame into memory.
+	fn load(&mut self, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		if let Entry::Stored(Slot::Root(root)) = self {
+			*self = Entry::Frame(Box::new(Frame::load(*root, diary_reader)?));
+		}
+		Ok(())
+	}
+	fn leaf(&self, diary_reader: &mut diary::Reader) -> io::Result<Leaf> {
+		match self {
+			Entry::Stored(Slot::KeyValue(_, leaf_pos)) => diary_reader.read_cached::<Leaf>(diary::Pos::at(*leaf_pos as usize)),
+			Entry::Leaf(_, leaf) => Ok(leaf.clone()),
+			_ => panic!("leaf called on non-leaf entry"),
+		}
+	}
+}
+
+impl Frame {
+	fn write(&mut self, key: &[u8], value: u32, slot_indexer: &mut impl SlotIndexer, depth: usize, diary_reader: &mut diary::Reader) -> io::Result<()> {
+		let n = slot_indexer.slot_index(depth) as usize;
+		self.entries[n].load(diary_reader)?;
+		let hash = slot_indexer.key();
+		let entry = match &mut self.entries[n] {
+			Entry::Frame(sub_frame) => {
+				sub_frame.write(key, value, slot_indexer, depth + 1, diary_reader)?;
+				self.changed = true;
+				return Ok(());
+			}
+			Entry::Stored(Slot::Empty) => Entry::Leaf(hash, Leaf::single(key, value)),
+			entry if entry.hash() == Some(hash) => Entry::Leaf(hash, entry.leaf(diary_reader)?.with_value(key, value)),
+			entry => {
+				let defender_hash = entry.hash().expect("Leaf entry");
+				let defender_index = slot_indexer.with_key(defender_hash).slot_index(depth + 1) as usize;
+				let mut sub_frame = Frame::empty();
+				sub_frame.entries[defender_index] = std::mem::replace(entry, Entry::Stored(Slot::Empty));
+				sub_frame.write(key, value, slot_indexer, depth + 1, diary_reader)?;
+				Entry::Frame(Box::new(sub_frame))
+			}
+		};
+		self.entries[n] = entry;
+		self.changed = true;
+		Ok(())
+	}
+
+	/// Removes the key and reports whether the frame changed. Sub-frames left empty are
+	/// dropped, and sub-frames left with a single leaf are collapsed into this frame.
+	fn remove(&mut self, key: &[u8], slot_indexer: &mut impl SlotIndexer, depth: usize, diary_reader: &mut diary::Reader) -> io::Result<bool> {
+		let n = slot_indexer.slot_index(depth) as usize;
+		self.entries[n].load(diary_reader)?;
+		let hash = slot_indexer.key();
+		let replacement = match &mut self.entries[n] {
+			Entry::Frame(sub_frame) => {
+				if !sub_frame.remove(key, slot_indexer, depth + 1, diary_reader)? {
+					return Ok(false);
+				}
+				sub_frame.collapse()
+			}
+			entry if entry.hash() == Some(hash) => {
+				let leaf = entry.leaf(diary_reader)?;
+				if leaf.value(key).is_none() {
+					return Ok(false);
+				}
+				let leaf = leaf.without_key(key);
+				match leaf.entries.is_empty() {
+					true => Some(Entry::Stored(Slot::Empty)),
+					false => Some(Entry::Leaf(hash, leaf)),
+				}
+			}
+			_ => return Ok(false),
+		};
+		if let Some(replacement) = replacement {
+			self.entries[n] = replacement;
+		}
+		self.changed = true;
+		Ok(true)
+	}
+
+	/// Takes the entry replacing this frame in its parent when the frame holds no entries
+	/// or a single leaf.
+	fn collapse(&mut self) -> Option<Entry> {
+		let mut occupied = self.entries.iter_mut().filter(|entry| !matches!(entry, Entry::Stored(Slot::Empty)));
+		match (occupied.next(), occupied.next()) {
+			(None, _) => Some(Entry::Stored(Slot::Empty)),
+			(Some(entry), None) if entry.is_leaf() => Some(std::mem::replace(entry, Entry::Stored(Slot::Empty))),
+			_ => None,
+		}
+	}
+
+	fn commit(self, diary_writer: &mut diary::Writer) -> io::Result<Root> {
+		if !self.changed {
+			return Ok(self.origin);
+		}
+		let mut slots = Vec::new();
+		let mut mask = 0u32;
+		for (n, entry) in self.entries.into_iter().enumerate() {
+			let slot = match entry {
+				Entry::Stored(slot) => slot,
+				Entry::Leaf(hash, leaf) => {
+					let (leaf_pos, _size) = diary_writer.write(&leaf)?;
+					Slot::KeyValue(hash, leaf_pos.u32())
+				}
+				Entry::Frame(sub_frame) => Slot::Root(sub_frame.commit(diary_writer)?),
+			};
+			if slot != Slot::Empty {
+				slots.push(slot);
+				mask |= SlotIndex::at(n).as_mask();
+			}
+		}
+		if slots.is_empty() {
+			Ok(Root::ZERO)
+		} else {
+			let root = frame::Writer::new(diary_writer).write_frame(&slots, mask)?;
+			require_empty_high_bit_in_position(root)
+		}
+	}
+
+	fn load(root: Root, diary_reader: &mut diary::Reader) -> io::Result<Frame> {
+		let mut frame = Frame { origin: root, changed: false, entries: Frame::empty().entries };
+		if root.mask != 0 {
+			let slots = Reader::new(root).read_frame(root, diary_reader)?;
+			for n in SlotIndex::RANGE {
+				if let Some(offset) = root.slot_offset(SlotIndex::at(n)) {
+					frame.entries[n] = Entry::Stored(slots[offset]);
+				}
+			}
+		}
+		Ok(frame)
+	}
+
+	fn empty() -> Frame {
+		let entries = SlotIndex::RANGE.map(|_| Entry::Stored(Slot::Empty)).collect();
+		Frame { origin: Root::ZERO, changed: true, entries }
+	}
+}
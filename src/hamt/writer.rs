@@ -4,20 +4,24 @@ use std::io::ErrorKind;
 use crate::diary;
 use crate::hamt::frame::{SlotIndex, WriteSlot};
 use crate::hamt::frame;
+use crate::hamt::leaf::Leaf;
 use crate::hamt::reader::Reader;
 use crate::hamt::root::Root;
 use crate::hamt::slot::Slot;
 use crate::hamt::slot_indexer::SlotIndexer;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
 	use std::error::Error;
 	use std::path::PathBuf;
 
 	use crate::diary::Diary;
 	use crate::hamt::data::{fixture::ZeroThenKeySlotIndexer};
+	use crate::hamt::frame::SlotIndex;
 	use crate::hamt::reader::Reader;
 	use crate::hamt::Root;
+	use crate::hamt::slot::Slot;
 	use crate::hamt::slot_indexer::SlotIndexer;
 	use crate::hamt::writer::Writer;
 
@@ -34,8 +38,8 @@ mod tests {
 		let diary = Diary::load(&path)?;
 		let mut diary_reader = diary.reader()?;
 		let reader = Reader::new(root);
-		let value1 = reader.read(&mut slot_indexer1, &mut diary_reader)?;
-		let value2 = reader.read(&mut slot_indexer2, &mut diary_reader)?;
+		let value1 = reader.read(&[1], &mut slot_indexer1, &mut diary_reader)?;
+		let value2 = reader.read(&[2], &mut slot_indexer2, &mut diary_reader)?;
 		assert_eq!((value1, value2), (Some(10), Some(20)));
 		Ok(())
 	}
@@ -56,13 +60,87 @@ mod tests {
 		let diary = Diary::load(&path)?;
 		let mut diary_reader = diary.reader()?;
 		let reader = Reader::new(new_root);
-		let value1 = reader.read(&mut slot_indexer1, &mut diary_reader)?;
-		let value2 = reader.read(&mut slot_indexer2, &mut diary_reader)?;
-		let value3 = reader.read(&mut slot_indexer3, &mut diary_reader)?;
+		let value1 = reader.read(&[1], &mut slot_indexer1, &mut diary_reader)?;
+		let value2 = reader.read(&[2], &mut slot_indexer2, &mut diary_reader)?;
+		let value3 = reader.read(&[3], &mut slot_indexer3, &mut diary_reader)?;
 		assert_eq!((value1, value2, value3), (Some(10), Some(20), Some(30)));
 		Ok(())
 	}
 
+	#[test]
+	fn remove_clears_read() -> Result<(), Box<dyn Error>> {
+		let mut slot_indexer1 = ZeroThenKeySlotIndexer { key: 1, transition_depth: 1 };
+		let mut slot_indexer2 = ZeroThenKeySlotIndexer { key: 2, transition_depth: 1 };
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let (full_root, removed_root, empty_root) = {
+			let mut writer = Writer::new(Root::ZERO, &mut diary_writer);
+			writer.write(&[1], 10, &mut slot_indexer1)?;
+			let full_root = writer.write(&[2], 20, &mut slot_indexer2)?;
+			let removed_root = writer.remove(&[1], &mut slot_indexer1)?;
+			let empty_root = writer.remove(&[2], &mut slot_indexer2)?;
+			(full_root, removed_root, empty_root)
+		};
+		diary.commit(diary_writer.end_size());
+		let mut diary_reader = diary.reader()?;
+		let full = Reader::new(full_root);
+		let removed = Reader::new(removed_root);
+		assert_eq!(full.read(&[1], &mut slot_indexer1, &mut diary_reader)?, Some(10));
+		assert_eq!(removed.read(&[1], &mut slot_indexer1, &mut diary_reader)?, None);
+		assert_eq!(removed.read(&[2], &mut slot_indexer2, &mut diary_reader)?, Some(20));
+		assert_eq!(empty_root, Root::ZERO);
+		Ok(())
+	}
+
+	#[test]
+	fn remove_collapses_sub_frames() -> Result<(), Box<dyn Error>> {
+		let mut slot_indexer1 = ZeroThenKeySlotIndexer { key: 1, transition_depth: 3 };
+		let mut slot_indexer2 = ZeroThenKeySlotIndexer { key: 2, transition_depth: 3 };
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let (single_root, collapsed_root) = {
+			let mut writer = Writer::new(Root::ZERO, &mut diary_writer);
+			let single_root = writer.write(&[1], 10, &mut slot_indexer1)?;
+			// Second write pushes both values three sub-frames deep.
+			writer.write(&[2], 20, &mut slot_indexer2)?;
+			let collapsed_root = writer.remove(&[2], &mut slot_indexer2)?;
+			(single_root, collapsed_root)
+		};
+		diary.commit(diary_writer.end_size());
+		let mut diary_reader = diary.reader()?;
+		let reader = Reader::new(collapsed_root);
+		let slot = reader.read_slot(collapsed_root, SlotIndex::at(0), &mut diary_reader)?;
+		assert_eq!(collapsed_root.mask, single_root.mask);
+		assert!(matches!(slot, Slot::KeyValue(1, _)));
+		assert_eq!(reader.read(&[1], &mut slot_indexer1, &mut diary_reader)?, Some(10));
+		assert_eq!(reader.read(&[2], &mut slot_indexer2, &mut diary_reader)?, None);
+		Ok(())
+	}
+
+	#[test]
+	fn colliding_hashes_keep_both_keys() -> Result<(), Box<dyn Error>> {
+		let mut slot_indexer = ZeroThenKeySlotIndexer { key: 1, transition_depth: 1 };
+		let diary = Diary::temp()?;
+		let mut diary_writer = diary.writer()?;
+		let (both_root, removed_root) = {
+			let mut writer = Writer::new(Root::ZERO, &mut diary_writer);
+			writer.write(&[1], 10, &mut slot_indexer)?;
+			let both_root = writer.write(&[2], 20, &mut slot_indexer)?;
+			let removed_root = writer.remove(&[1], &mut slot_indexer)?;
+			(both_root, removed_root)
+		};
+		diary.commit(diary_writer.end_size());
+		let mut diary_reader = diary.reader()?;
+		let both = Reader::new(both_root);
+		let removed = Reader::new(removed_root);
+		assert_eq!(both.read(&[1], &mut slot_indexer, &mut diary_reader)?, Some(10));
+		assert_eq!(both.read(&[2], &mut slot_indexer, &mut diary_reader)?, Some(20));
+		assert_eq!(both.read(&[3], &mut slot_indexer, &mut diary_reader)?, None);
+		assert_eq!(removed.read(&[1], &mut slot_indexer, &mut diary_reader)?, None);
+		assert_eq!(removed.read(&[2], &mut slot_indexer, &mut diary_reader)?, Some(20));
+		Ok(())
+	}
+
 	#[test]
 	fn single_write_changes_read() -> Result<(), Box<dyn Error>> {
 		let mut slot_indexer = ZeroThenKeySlotIndexer { key: 1, transition_depth: 1 };
@@ -72,7 +150,7 @@ mod tests {
 		let diary = Diary::load(&path)?;
 		let mut diary_reader = diary.reader()?;
 		let reader = Reader::new(new_root);
-		let reading = reader.read(&mut slot_indexer, &mut diary_reader)?;
+		let reading = reader.read(&[1], &mut slot_indexer, &mut diary_reader)?;
 		assert_eq!(reading, Some(17));
 		Ok(())
 	}
@@ -83,7 +161,7 @@ mod tests {
 		let new_root = {
 			let mut writer = Writer::new(root, &mut diary_writer);
 			for (slot_indexer, value) in tasks {
-				writer.write(value, slot_indexer)?;
+				writer.write(&[slot_indexer.key() as u8], value, slot_indexer)?;
 			}
 			writer.root
 		};
@@ -106,7 +184,9 @@ enum WriteRoot {
 }
 
 impl<'a> Writer<'a> {
-	pub fn write(&mut self, value: u32, slot_indexer: &mut impl SlotIndexer) -> io::Result<Root> {
+	/// Writes the value position for the full key. The slot indexer supplies the key's hash,
+	/// and keys whose hashes collide share a leaf.
+	pub fn write(&mut self, key: &[u8], value: u32, slot_indexer: &mut impl SlotIndexer) -> io::Result<Root> {
 		require_empty_high_bit(slot_indexer.key())?;
 		let mut diary_reader = self.diary_writer.reader()?;
 		let revisions = {
@@ -126,14 +206,16 @@ impl<'a> Writer<'a> {
 					Slot::KeyValue(defender_key, defender_value) => {
 						let attacker_key = slot_indexer.key();
 						if defender_key == attacker_key {
-							revisions.push(WriteRoot::ReviseWithValue(root, slot_index, Slot::KeyValue(defender_key, value)));
+							let leaf = diary_reader.read::<Leaf>(diary::Pos::at(defender_value as usize))?;
+							let (leaf_pos, _size) = self.diary_writer.write(&leaf.with_value(key, value))?;
+							revisions.push(WriteRoot::ReviseWithValue(root, slot_index, Slot::KeyValue(defender_key, leaf_pos.u32())));
 							done = true;
 						} else {
 							revisions.push(WriteRoot::ReviseWithSubRoot(root, slot_index));
 							let mut resolution_indices = None;
 							let mut defender_indexer = slot_indexer.with_key(defender_key);
 							let mut resolution_depth = depth;
-							while resolution_indices == None {
+							while resolution_indices.is_none() {
 								resolution_depth += 1;
 								let defender_index = SlotIndex::at(defender_indexer.slot_index(resolution_depth) as usize);
 								let attacker_index = SlotIndex::at(slot_indexer.slot_index(resolution_depth) as usize);
@@ -144,15 +226,17 @@ impl<'a> Writer<'a> {
 								}
 							}
 							let (attacker_index, defender_index) = resolution_indices.unwrap();
+							let (leaf_pos, _size) = self.diary_writer.write(&Leaf::single(key, value))?;
 							revisions.push(WriteRoot::AddWithValues(
-								attacker_index, Slot::KeyValue(attacker_key, value),
+								attacker_index, Slot::KeyValue(attacker_key, leaf_pos.u32()),
 								defender_index, Slot::KeyValue(defender_key, defender_value),
 							));
 							done = true;
 						}
 					}
 					Slot::Empty => {
-						revisions.push(WriteRoot::ReviseWithValue(root, slot_index, Slot::KeyValue(slot_indexer.key(), value)));
+						let (leaf_pos, _size) = self.diary_writer.write(&Leaf::single(key, value))?;
+						revisions.push(WriteRoot::ReviseWithValue(root, slot_index, Slot::KeyValue(slot_indexer.key(), leaf_pos.u32())));
 						done = true;
 					}
 				}
@@ -165,19 +249,19 @@ impl<'a> Writer<'a> {
 		for revision in revisions {
 			match revision {
 				WriteRoot::ReviseWithValue(old_root, slot_index, new_slot) => {
-					let mut frame_reader = frame::Reader::new(old_root, &mut diary_reader)?;
+					let frame_reader = frame::Reader::new(old_root, &diary_reader)?;
 					let new_root = writer.write_revised_root(
 						WriteSlot { slot: new_slot, slot_index },
-						&mut frame_reader,
+						&frame_reader,
 					)?;
 					current_root = require_empty_high_bit_in_position(new_root)?;
 				}
 				WriteRoot::ReviseWithSubRoot(old_root, slot_index) => {
 					let new_slot = Slot::Root(current_root);
-					let mut frame_reader = frame::Reader::new(old_root, &mut diary_reader)?;
+					let frame_reader = frame::Reader::new(old_root, &diary_reader)?;
 					let new_root = writer.write_revised_root(
 						WriteSlot { slot: new_slot, slot_index },
-						&mut frame_reader,
+						&frame_reader,
 					)?;
 					current_root = require_empty_high_bit_in_position(new_root)?;
 				}
@@ -200,14 +284,79 @@ impl<'a> Writer<'a> {
 		self.root = current_root;
 		Ok(self.root)
 	}
+	/// Removes the key from the tree by path-copying every frame from the root down to the
+	/// key's slot. Frames left with no slots are dropped from their parents, and sub-frames
+	/// left with a single key-value are collapsed into their parents, so the tree is as
+	/// shallow as if the key had never been written. An empty tree becomes `Root::ZERO`.
+	/// Removing an absent key leaves the root unchanged.
+	pub fn remove(&mut self, key: &[u8], slot_indexer: &mut impl SlotIndexer) -> io::Result<Root> {
+		require_empty_high_bit(slot_indexer.key())?;
+		let mut diary_reader = self.diary_writer.reader()?;
+		let mut path = Vec::new();
+		let mut replacement = Slot::Empty;
+		{
+			let reader = Reader::new(self.root);
+			let mut depth = 0;
+			let mut root = self.root;
+			loop {
+				let slot_index = SlotIndex::at(slot_indexer.slot_index(depth) as usize);
+				match reader.read_slot(root, slot_index, &mut diary_reader)? {
+					Slot::Root(sub_root) => {
+						path.push((root, slot_index));
+						root = sub_root;
+						depth += 1;
+					}
+					Slot::KeyValue(hash, leaf_pos) if hash == slot_indexer.key() => {
+						let leaf = diary_reader.read::<Leaf>(diary::Pos::at(leaf_pos as usize))?;
+						if leaf.value(key).is_none() {
+							return Ok(self.root);
+						}
+						let leaf = leaf.without_key(key);
+						if !leaf.entries.is_empty() {
+							let (leaf_pos, _size) = self.diary_writer.write(&leaf)?;
+							replacement = Slot::KeyValue(hash, leaf_pos.u32());
+						}
+						path.push((root, slot_index));
+						break;
+					}
+					Slot::KeyValue(_, _) | Slot::Empty => return Ok(self.root),
+				}
+			}
+		}
+		let reader = Reader::new(self.root);
+		let mut writer = frame::Writer::new(self.diary_writer);
+		for (depth, (old_root, slot_index)) in path.into_iter().enumerate().rev() {
+			let other_mask = old_root.mask & !slot_index.as_mask();
+			let is_sub_frame = depth > 0;
+			replacement = match replacement {
+				Slot::Empty if other_mask == 0 => Slot::Empty,
+				Slot::Empty if is_sub_frame && other_mask.count_ones() == 1 => {
+					let other_index = SlotIndex::at(other_mask.trailing_zeros() as usize);
+					match reader.read_slot(old_root, other_index, &mut diary_reader)? {
+						Slot::KeyValue(key, value) => Slot::KeyValue(key, value),
+						_ => write_revised_sub_root(&mut writer, old_root, WriteSlot { slot: Slot::Empty, slot_index }, &diary_reader)?,
+					}
+				}
+				Slot::KeyValue(key, value) if is_sub_frame && other_mask == 0 => Slot::KeyValue(key, value),
+				slot => write_revised_sub_root(&mut writer, old_root, WriteSlot { slot, slot_index }, &diary_reader)?,
+			};
+		}
+		self.root = match replacement {
+			Slot::Root(root) => root,
+			_ => Root::ZERO,
+		};
+		Ok(self.root)
+	}
 	pub fn new(root: Root, diary_writer: &'a mut diary::Writer) -> Self { Writer { root, diary_writer } }
 }
 
-pub(crate) trait WriteContext {
-	fn slot_indexer(&self, key: u32) -> Box<dyn SlotIndexer>;
+fn write_revised_sub_root(writer: &mut frame::Writer, old_root: Root, write_slot: WriteSlot, diary_reader: &diary::Reader) -> io::Result<Slot> {
+	let frame_reader = frame::Reader::new(old_root, diary_reader)?;
+	let new_root = writer.write_revised_root(write_slot, &frame_reader)?;
+	Ok(Slot::Root(require_empty_high_bit_in_position(new_root)?))
 }
 
-fn require_empty_high_bit_in_position(root: Root) -> io::Result<Root> {
+pub(crate) fn require_empty_high_bit_in_position(root: Root) -> io::Result<Root> {
 	require_empty_high_bit(root.pos).map(|_| root)
 }
 
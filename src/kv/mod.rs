@@ -26,12 +26,13 @@ pub trait Key: Hash {}
 
 pub trait Value: Sized {
 	fn to_value_string(&self) -> String;
+	#[allow(clippy::ptr_arg)]
 	fn from_value_string(s: &String) -> Result<Self, Box<dyn Error>>;
 }
 
 pub fn open(name: &str, folder: &Path) -> Result<Store, Box<dyn Error>> {
 	//! Open a key-value store with the given name in the specified folder.
-	let echo = Echo::connect(name, folder);
+	let echo = Echo::connect(name, folder)?;
 	Ok(Store { echo })
 }
 
@@ -39,7 +40,7 @@ impl Catalog {
 	pub fn read<K: Key, V: Value, F: Fn() -> V>(&self, key: &K, fallback: F) -> Result<V, Box<dyn Error>> {
 		//! Read the value at key.
 		let object_id = key_object_id(key);
-		let target = self.chamber.target_at_object_point_or_none(&object_id, &VALUE_POINT);
+		let target = self.chamber.try_target_at_object_point_or_none(&object_id, &VALUE_POINT)?;
 		match target {
 			None => Ok(fallback()),
 			Some(ref target) => if let Target::String(ref s) = target {
@@ -63,6 +64,14 @@ impl Store {
 		})?;
 		Ok(())
 	}
+	pub fn remove(&self, key: &impl Key) -> Result<(), Box<dyn Error>> {
+		//! Remove the value at a key.
+		self.echo.write(|echo_writer| {
+			let object_id = key_object_id(key);
+			echo_writer.retract(&object_id, &VALUE_POINT);
+		})?;
+		Ok(())
+	}
 	pub fn catalog(&self) -> Result<Catalog, Box<dyn Error>> {
 		//! Acquire a reader for the current state of the store.
 		let chamber = self.echo.chamber()?;
@@ -2,18 +2,20 @@
 //!
 //! ```
 //! use echo_lib::Echo;
-//! let echo = Echo::connect("my-app", &std::env::temp_dir());
+//! let echo = Echo::connect("my-app", &std::env::temp_dir()).unwrap();
 //! ```
 extern crate rand;
 
 pub use self::chamber::*;
 pub use self::core::*;
-pub use self::echo::Echo;
+pub use self::error::EchoError;
+pub use self::echo::{CacheOptions, Change, ChangeFilter, Durability, Echo, EchoOptions, Locking, ReadOnlyEcho, Recovery, Subscription, Transaction, TransactionRecord, Transactions, WriteError};
 pub use self::object::*;
 
 mod chamber;
 mod core;
 mod echo;
+mod error;
 mod object;
 pub mod util;
 pub mod hamt;
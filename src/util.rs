@@ -1,10 +1,10 @@
 use std::io;
-use std::io::ErrorKind;
 use std::path::PathBuf;
 use std::string::FromUtf8Error;
-use std::sync::mpsc::RecvError;
 use std::error::Error;
 
+use crate::EchoError;
+
 pub fn temp_dir(prefix: &str) -> Result<PathBuf, Box<dyn Error>> {
 	let mut path = std::env::temp_dir();
 	let string = unique_name(prefix);
@@ -30,12 +30,12 @@ pub(crate) fn is_high_bit_set(n: u32) -> bool { n & 0x80000000 != 0 }
 
 pub(crate) fn big_end_first_2(n: u16, buf: &mut [u8; 2]) {
 	buf[0] = (n >> 8) as u8;
-	buf[1] = (n >> 0) as u8;
+	buf[1] = n as u8;
 }
 
 pub(crate) fn u16_of_buf(buf: &[u8; 2]) -> u16 {
 	let c0 = (buf[0] as u16) << 8;
-	let c1 = (buf[1] as u16) << 0;
+	let c1 = buf[1] as u16;
 	c0 | c1
 }
 
@@ -43,7 +43,15 @@ pub(crate) fn big_end_first_4(n: u32, buf: &mut [u8; 4]) {
 	buf[0] = (n >> 24) as u8;
 	buf[1] = (n >> 16) as u8;
 	buf[2] = (n >> 8) as u8;
-	buf[3] = (n >> 0) as u8;
+	buf[3] = n as u8;
+}
+
+pub(crate) fn u32_of_buf(buf: &[u8; 4]) -> u32 {
+	let c0 = (buf[0] as u32) << 24;
+	let c1 = (buf[1] as u32) << 16;
+	let c2 = (buf[2] as u32) << 8;
+	let c3 = buf[3] as u32;
+	c0 | c1 | c2 | c3
 }
 
 pub(crate) fn big_end_first_8(n: u64, buf: &mut [u8; 8]) {
@@ -54,18 +62,18 @@ pub(crate) fn big_end_first_8(n: u64, buf: &mut [u8; 8]) {
 	buf[4] = (n >> 24) as u8;
 	buf[5] = (n >> 16) as u8;
 	buf[6] = (n >> 8) as u8;
-	buf[7] = (n >> 0) as u8;
+	buf[7] = n as u8;
 }
 
 pub(crate) fn u64_of_buf(buf: &[u8; 8]) -> u64 {
-	let c0 = (buf[0] as u64) << 48;
-	let c1 = (buf[1] as u64) << 40;
+	let c0 = (buf[0] as u64) << 56;
+	let c1 = (buf[1] as u64) << 48;
 	let c2 = (buf[2] as u64) << 40;
 	let c3 = (buf[3] as u64) << 32;
 	let c4 = (buf[4] as u64) << 24;
 	let c5 = (buf[5] as u64) << 16;
 	let c6 = (buf[6] as u64) << 8;
-	let c7 = (buf[7] as u64) << 0;
+	let c7 = buf[7] as u64;
 	c0 | c1 | c2 | c3 | c4 | c5 | c6 | c7
 }
 
@@ -78,21 +86,35 @@ pub(crate) fn u32x2_of_buf(buf: &[u8; 8]) -> U32x2 {
 			(buf[0] as u32) << 24,
 			(buf[1] as u32) << 16,
 			(buf[2] as u32) << 8,
-			(buf[3] as u32) << 0
+			buf[3] as u32
 		].iter().fold(0, |sum, next| sum | *next),
 		[
 			(buf[4] as u32) << 24,
 			(buf[5] as u32) << 16,
 			(buf[6] as u32) << 8,
-			(buf[7] as u32) << 0
+			buf[7] as u32
 		].iter().fold(0, |sum, next| sum | *next)
 	)
 }
 
-pub(crate) fn io_error(error: RecvError) -> io::Error {
-	io::Error::new(ErrorKind::Other, error.to_string())
+/// Computes the CRC-32 (IEEE) checksum of the bytes.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for byte in bytes {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			let mask = (crc & 1).wrapping_neg();
+			crc = (crc >> 1) ^ (0xEDB88320 & mask);
+		}
+	}
+	!crc
+}
+
+/// Reports a closed channel as the writer thread having stopped.
+pub(crate) fn writer_gone<E>(_error: E) -> io::Error {
+	io::Error::other(EchoError::WriterGone)
 }
 
 pub(crate) fn io_error_of_utf8(error: FromUtf8Error) -> io::Error {
-	io::Error::new(ErrorKind::Other, error.to_string())
+	io::Error::other(error.to_string())
 }
\ No newline at end of file
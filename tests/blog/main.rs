@@ -12,8 +12,8 @@ fn it_works() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-fn review(echo_name: &String) -> Result<(), Box<dyn Error>> {
-	let echo = Echo::connect(&echo_name, &temp_dir());
+fn review(echo_name: &str) -> Result<(), Box<dyn Error>> {
+	let echo = Echo::connect(echo_name, &temp_dir())?;
 	let chamber = echo.chamber().unwrap();
 	let blogger_id = blogger::read(&echo.chamber()?).unwrap().unwrap();
 	let blog_id = blog::create_if_none(&blogger_id, &echo).unwrap();
@@ -23,8 +23,8 @@ fn review(echo_name: &String) -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
-fn mutate(echo_name: &String) -> Result<(), Box<dyn Error>> {
-	let echo = Echo::connect(&echo_name, &temp_dir());
+fn mutate(echo_name: &str) -> Result<(), Box<dyn Error>> {
+	let echo = Echo::connect(echo_name, &temp_dir())?;
 	let blogger_id = blogger::create_if_none(&echo)?;
 	let blog_id = blog::create_if_none(&blogger_id, &echo)?;
 	post::create("Elephant ears", "Elephant ears are big.", &blog_id, &echo)?;
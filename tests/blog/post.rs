@@ -1,18 +1,16 @@
-use std::io;
-
-use echo_lib::{Chamber, Echo, ObjectId, Point, Target};
+use echo_lib::{Chamber, Echo, EchoError, ObjectId, Point, Target};
 
 pub const TITLE: &Point = &Point::Static { aspect: "BlogPost", name: "title" };
 pub const BODY: &Point = &Point::Static { aspect: "BlogPost", name: "body" };
 pub const BLOG_ID: &Point = &Point::Static { aspect: "BlogPost", name: "blog" };
 
-pub fn read_ordered(blog_id: &ObjectId, chamber: &Chamber) -> io::Result<Vec<ObjectId>> {
+pub fn read_ordered(blog_id: &ObjectId, chamber: &Chamber) -> Result<Vec<ObjectId>, EchoError> {
 	let mut posts = chamber.objects_with_property(BLOG_ID, &Target::Object(blog_id.to_owned()))?;
 	posts.sort_by_key(|it| chamber.string(it, TITLE));
 	Ok(posts)
 }
 
-pub fn create(title: &str, body: &str, blog_id: &ObjectId, echo: &Echo) -> io::Result<ObjectId> {
+pub fn create(title: &str, body: &str, blog_id: &ObjectId, echo: &Echo) -> Result<ObjectId, EchoError> {
 	echo.write(|write| {
 		let post_id = write.new_object_id("blog-post");
 		write.write_object_properties(&post_id, vec![
@@ -1,8 +1,12 @@
 use std::{io, thread};
 use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::channel;
 
-use echo_lib::{Echo, Object, ObjectFilter, ObjectId, Point, Say, Target, Writable};
+use echo_lib::{ChangeFilter, Durability, Echo, EchoError, EchoOptions, Locking, Object, Recovery, ObjectFilter, ObjectId, Point, Say, Sayer, Target, Writable, WriteError};
+use echo_lib::bytes::ReadBytes;
 use echo_lib::util::unique_name;
 
 const COUNT: Point = Point::Static { name: "count", aspect: "Counter" };
@@ -45,7 +49,7 @@ impl<'a> ObjectFilter<'a> for Counter {
 fn filter() {
 	let counter = Counter::new("card-counter", 7, 56);
 	let mut chamber = {
-		let echo = Echo::connect(&unique_name("point-holder"), &std::env::temp_dir());
+		let echo = Echo::connect(&unique_name("point-holder"), &std::env::temp_dir()).unwrap();
 		echo.write(|txn| txn.writable(&counter)).unwrap();
 		echo.chamber().unwrap()
 	};
@@ -58,7 +62,7 @@ fn filter() {
 
 #[test]
 fn multi_thread() -> Result<(), Box<dyn Error>> {
-	let echo = Echo::connect(&unique_name("test-multi-thread"), &std::env::temp_dir());
+	let echo = Echo::connect(&unique_name("test-multi-thread"), &std::env::temp_dir())?;
 	let job1 = {
 		let echo = echo.clone();
 		thread::spawn(move || {
@@ -92,19 +96,19 @@ fn multi_thread() -> Result<(), Box<dyn Error>> {
 fn double_reconnect() -> Result<(), Box<dyn Error>> {
 	let path = {
 		let path = unique_name("echo-test-");
-		let echo = Echo::connect(&path, &std::env::temp_dir());
+		let echo = Echo::connect(&path, &std::env::temp_dir())?;
 		echo.write(|write| {
 			write.target(Target::Number(3));
 		})?;
 		path
 	};
 	{
-		let echo = Echo::connect(&path, &std::env::temp_dir());
+		let echo = Echo::connect(&path, &std::env::temp_dir())?;
 		echo.write(|write| {
 			write.target(Target::Number(10));
 		})?;
 	}
-	let echo = Echo::connect(&path, &std::env::temp_dir());
+	let echo = Echo::connect(&path, &std::env::temp_dir())?;
 	let mut chamber = echo.chamber()?;
 	assert_eq!(chamber.target_or_none(), Some(Target::Number(10)));
 	Ok(())
@@ -114,14 +118,14 @@ fn double_reconnect() -> Result<(), Box<dyn Error>> {
 fn reconnect() -> Result<(), Box<dyn Error>> {
 	let path = {
 		let path = unique_name("echo-test-");
-		let echo = Echo::connect(&path, &std::env::temp_dir());
+		let echo = Echo::connect(&path, &std::env::temp_dir())?;
 		echo.write(|write| {
 			write.target(Target::Number(3));
 			write.target(Target::Number(10));
 		})?;
 		path
 	};
-	let echo = Echo::connect(&path, &std::env::temp_dir());
+	let echo = Echo::connect(&path, &std::env::temp_dir())?;
 	let mut chamber = echo.chamber()?;
 	assert_eq!(chamber.target_or_none(), Some(Target::Number(10)));
 	Ok(())
@@ -131,7 +135,7 @@ fn reconnect() -> Result<(), Box<dyn Error>> {
 fn objects_with_point() -> Result<(), Box<dyn Error>> {
 	let dracula = ObjectId::new("Dracula");
 	let bo_peep = ObjectId::new("Bo Peep");
-	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir());
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
 	echo.write(|shout| {
 		shout.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3)), ]);
 		shout.write_object_properties(&bo_peep, vec![(&COUNT, Target::Number(7)), ]);
@@ -145,7 +149,7 @@ fn objects_with_point() -> Result<(), Box<dyn Error>> {
 #[test]
 fn object_attributes() -> Result<(), Box<dyn Error>> {
 	let dracula = ObjectId::String("Dracula".into());
-	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir());
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
 	echo.write(|shout| {
 		shout.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]);
 	})?;
@@ -156,7 +160,7 @@ fn object_attributes() -> Result<(), Box<dyn Error>> {
 
 #[test]
 fn attributes() -> Result<(), Box<dyn Error>> {
-	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir());
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
 	echo.write(|shout| {
 		shout.attributes(vec![
 			(&MAX_COUNT, Target::Number(100)),
@@ -173,7 +177,7 @@ fn attributes() -> Result<(), Box<dyn Error>> {
 
 #[test]
 fn target() -> Result<(), Box<dyn Error>> {
-	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir());
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
 	let mut old_chamber = echo.chamber()?;
 	echo.write(|write| {
 		write.target(Target::Number(3))
@@ -183,3 +187,552 @@ fn target() -> Result<(), Box<dyn Error>> {
 	assert_eq!(old_chamber.target_or_none(), None);
 	Ok(())
 }
+
+#[test]
+fn retract() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let bo_peep = ObjectId::new("Bo Peep");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| {
+		write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3)), (&MAX_COUNT, Target::Number(10))]);
+		write.write_object_properties(&bo_peep, vec![(&COUNT, Target::Number(7))]);
+	})?;
+	let old_chamber = echo.chamber()?;
+	echo.write(|write| {
+		write.retract(&dracula, &COUNT);
+		write.retract_object(&bo_peep);
+	})?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.target_at_object_point_or_none(&dracula, &COUNT), None);
+	assert_eq!(chamber.target_at_object_point_or_none(&dracula, &MAX_COUNT), Some(Target::Number(10)));
+	assert_eq!(chamber.target_at_object_point_or_none(&bo_peep, &COUNT), None);
+	assert_eq!(chamber.objects_with_point(&COUNT)?, Vec::<ObjectId>::new());
+	assert_eq!(chamber.objects_with_point(&MAX_COUNT)?, vec![dracula.clone()]);
+	assert_eq!(old_chamber.target_at_object_point_or_none(&dracula, &COUNT), Some(Target::Number(3)));
+	let mut old_objects = old_chamber.objects_with_point(&COUNT)?;
+	old_objects.sort();
+	assert_eq!(old_objects, vec![bo_peep, dracula]);
+	Ok(())
+}
+
+#[test]
+fn transact_reads_own_says() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	let (count, objects) = echo.transact(|txn| {
+		txn.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]);
+		let count = txn.target_at_object_point_or_none(&dracula, &COUNT)?;
+		let objects = txn.objects_with_point(&COUNT)?;
+		Ok((count, objects))
+	})?;
+	assert_eq!(count, Some(Target::Number(3)));
+	assert_eq!(objects, vec![dracula.clone()]);
+	assert_eq!(echo.chamber()?.target_at_object_point_or_none(&dracula, &COUNT), Some(Target::Number(3)));
+	Ok(())
+}
+
+#[test]
+fn transact_retries_after_conflict() -> Result<(), Box<dyn Error>> {
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| write.attributes(vec![(&COUNT, Target::Number(1))]))?;
+	let attempts = AtomicUsize::new(0);
+	echo.transact(|txn| {
+		let count = txn.target_at_object_point_or_none(&ObjectId::Unit, &COUNT)?.unwrap().as_number();
+		if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+			// A concurrent commit lands between this read and the transaction's commit.
+			echo.write(|write| write.attributes(vec![(&COUNT, Target::Number(10))]))?;
+		}
+		txn.attributes(vec![(&COUNT, Target::Number(count + 1))]);
+		Ok(())
+	})?;
+	assert_eq!(attempts.load(Ordering::SeqCst), 2);
+	let count = echo.chamber()?.target_at_object_point_or_none(&ObjectId::Unit, &COUNT);
+	assert_eq!(count, Some(Target::Number(11)));
+	Ok(())
+}
+
+#[test]
+fn try_write_discards_aborted_says() -> Result<(), Box<dyn Error>> {
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	let aborted = echo.try_write(|write| {
+		write.attributes(vec![(&COUNT, Target::Number(200))]);
+		Err::<(), _>("count exceeds max count")
+	});
+	assert!(matches!(aborted, Err(WriteError::Aborted("count exceeds max count"))));
+	let written = echo.try_write(|write| {
+		write.attributes(vec![(&COUNT, Target::Number(20))]);
+		Ok::<_, &str>(20)
+	});
+	assert!(matches!(written, Ok(20)));
+	let count = echo.chamber()?.target_at_object_point_or_none(&ObjectId::Unit, &COUNT);
+	assert_eq!(count, Some(Target::Number(20)));
+	Ok(())
+}
+
+#[test]
+fn chamber_at_version() -> Result<(), Box<dyn Error>> {
+	let path = unique_name("echo-test-");
+	{
+		let echo = Echo::connect(&path, &std::env::temp_dir())?;
+		assert!(echo.versions()?.is_empty());
+		echo.write(|write| write.target(Target::Number(3)))?;
+		echo.write(|write| write.target(Target::Number(10)))?;
+	}
+	let echo = Echo::connect(&path, &std::env::temp_dir())?;
+	echo.write(|write| write.target(Target::Number(17)))?;
+	let versions = echo.versions()?;
+	assert_eq!(versions.clone().collect::<Vec<_>>(), vec![1, 2, 3]);
+	let targets = versions
+		.map(|version| echo.chamber_at(version)?.try_target_or_none())
+		.collect::<Result<Vec<_>, EchoError>>()?;
+	assert_eq!(targets, vec![Some(Target::Number(3)), Some(Target::Number(10)), Some(Target::Number(17))]);
+	assert_eq!(echo.chamber()?.version(), 3);
+	assert!(echo.chamber_at(4).is_err());
+	Ok(())
+}
+
+#[test]
+fn history() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&MAX_COUNT, Target::Number(10))]))?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(4))]))?;
+	echo.write(|write| write.retract(&dracula, &COUNT))?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(5))]))?;
+	let history = echo.chamber()?.history(&dracula, &COUNT)?;
+	assert_eq!(history, vec![
+		(5, Some(Target::Number(5))),
+		(4, None),
+		(3, Some(Target::Number(4))),
+		(1, Some(Target::Number(3))),
+	]);
+	let old_history = echo.chamber_at(3)?.history(&dracula, &COUNT)?;
+	assert_eq!(old_history, vec![(3, Some(Target::Number(4))), (1, Some(Target::Number(3)))]);
+	Ok(())
+}
+
+#[test]
+fn sayers() -> Result<(), Box<dyn Error>> {
+	let alice = Sayer::Named("alice".into());
+	let bob = Sayer::Named("bob".into());
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write_as(&alice, |write| {
+		write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3)), (&MAX_COUNT, Target::Number(10))]);
+	})?;
+	echo.write_as(&bob, |write| {
+		write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(4))]);
+	})?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.sayer_at_object_point(&dracula, &COUNT)?, Some(bob.clone()));
+	assert_eq!(chamber.sayer_at_object_point(&dracula, &MAX_COUNT)?, Some(alice.clone()));
+	assert_eq!(chamber.sayer_at_object_point(&ObjectId::Unit, &COUNT)?, None);
+	let alice_says = chamber.says_by(&alice)?;
+	assert_eq!(alice_says, vec![Say { sayer: alice, object: dracula.clone(), point: MAX_COUNT, target: Some(Target::Number(10)) }]);
+	let bob_says = chamber.says_by(&bob)?;
+	assert_eq!(bob_says, vec![Say { sayer: bob, object: dracula, point: COUNT, target: Some(Target::Number(4)) }]);
+	Ok(())
+}
+
+#[test]
+fn try_write_and_transact_as_sayer() -> Result<(), Box<dyn Error>> {
+	let alice = Sayer::Named("alice".into());
+	let bob = Sayer::Named("bob".into());
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.try_write_as(&alice, |write| {
+		write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]);
+		Ok::<_, io::Error>(())
+	})?;
+	echo.transact_as(&bob, |transaction| {
+		transaction.write_object_properties(&dracula, vec![(&MAX_COUNT, Target::Number(10))]);
+		Ok(())
+	})?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.sayer_at_object_point(&dracula, &COUNT)?, Some(alice));
+	assert_eq!(chamber.sayer_at_object_point(&dracula, &MAX_COUNT)?, Some(bob));
+	Ok(())
+}
+
+#[test]
+fn transactions() -> Result<(), Box<dyn Error>> {
+	const REASON: Point = Point::Static { name: "reason", aspect: "Audit" };
+	let alice = Sayer::Named("alice".into());
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3)), (&MAX_COUNT, Target::Number(10))]))?;
+	echo.write_as(&alice, |write| {
+		write.retract_object(&dracula);
+		write.metadata(&REASON, Target::String("reset".into()));
+	})?;
+	let records = echo.transactions(..)?.collect::<Result<Vec<_>, EchoError>>()?;
+	assert_eq!(records.len(), 2);
+	assert_eq!((records[0].version, &records[0].sayer, records[0].says.len()), (1, &Sayer::Unit, 2));
+	assert!(records[0].timestamp > 0 && records[0].timestamp <= records[1].timestamp);
+	let mut retracted_points = records[1].says.iter()
+		.map(|say| (say.sayer.clone(), say.point.clone(), say.target.clone()))
+		.collect::<Vec<_>>();
+	retracted_points.sort_by_key(|(_, point, _)| point == &COUNT);
+	assert_eq!(retracted_points, vec![(alice.clone(), MAX_COUNT, None), (alice.clone(), COUNT, None)]);
+	assert_eq!((records[1].version, &records[1].sayer), (2, &alice));
+	assert_eq!(records[1].metadata, vec![(REASON, Target::String("reset".into()))]);
+	let later = echo.transactions(2..)?.map(|record| record.map(|it| it.version)).collect::<Result<Vec<_>, EchoError>>()?;
+	assert_eq!(later, vec![2]);
+	Ok(())
+}
+
+#[test]
+fn subscribe() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	let all = echo.subscribe();
+	let max_counts = echo.subscribe_to(ChangeFilter::Point(MAX_COUNT));
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(1))]))?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(2)), (&MAX_COUNT, Target::Number(10))]))?;
+	let first = all.recv()?;
+	assert_eq!((first.version, first.says.len()), (1, 1));
+	assert_eq!(first.chamber.number(&dracula, &COUNT), 1);
+	let second = all.recv()?;
+	assert_eq!((second.version, second.says.len()), (2, 2));
+	let max_count = max_counts.recv()?;
+	assert_eq!(max_count.version, 2);
+	assert_eq!(max_count.says.iter().map(|say| say.point.clone()).collect::<Vec<_>>(), vec![MAX_COUNT]);
+	assert!(max_counts.try_recv().is_err());
+	Ok(())
+}
+
+#[test]
+fn group_commit() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let options = EchoOptions::default().durability(Durability::GroupCommit);
+	{
+		let echo = Echo::connect_with(&name, &std::env::temp_dir(), options)?;
+		let jobs = (0..8u64).map(|n| {
+			let echo = echo.clone();
+			thread::spawn(move || {
+				let object = ObjectId::String(format!("counter-{}", n));
+				echo.write(|write| write.write_object_properties(&object, vec![(&COUNT, Target::Number(n))]))
+			})
+		}).collect::<Vec<_>>();
+		for job in jobs {
+			job.join().unwrap()?;
+		}
+		assert_eq!(echo.versions()?, 1..=8);
+	}
+	let options = EchoOptions::default().durability(Durability::SyncEachCommit);
+	let echo = Echo::connect_with(&name, &std::env::temp_dir(), options)?;
+	echo.write(|write| write.write_object_properties(&ObjectId::new("Dracula"), vec![(&COUNT, Target::Number(9))]))?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.version(), 9);
+	assert_eq!(chamber.objects_with_point(&COUNT)?.len(), 9);
+	Ok(())
+}
+
+#[test]
+fn recover_torn_commit() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let folder = std::env::temp_dir().join(&name);
+	let dracula = ObjectId::new("Dracula");
+	{
+		let echo = Echo::connect(&name, &std::env::temp_dir())?;
+		echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+		assert!(echo.recovery()?.is_clean());
+	}
+	// A commit interrupted after writing diary bytes and part of its roots.
+	OpenOptions::new().append(true).open(folder.join("diary.dat"))?.write_all(&[7u8; 50])?;
+	OpenOptions::new().append(true).open(folder.join("roots.dat"))?.write_all(&[0u8; 41])?;
+	{
+		let echo = Echo::connect(&name, &std::env::temp_dir())?;
+		assert_eq!(echo.recovery()?, Recovery { torn_bytes: 5, dropped_commits: 1, truncated_diary_bytes: 50 });
+		assert_eq!(echo.chamber()?.number(&dracula, &COUNT), 3);
+		echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(4))]))?;
+	}
+	let echo = Echo::connect(&name, &std::env::temp_dir())?;
+	assert!(echo.recovery()?.is_clean());
+	assert_eq!(echo.chamber()?.number(&dracula, &COUNT), 4);
+	assert_eq!(echo.versions()?, 1..=2);
+	Ok(())
+}
+
+#[test]
+fn refuse_roots_without_good_commits() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let folder = std::env::temp_dir().join(&name);
+	{
+		let echo = Echo::connect(&name, &std::env::temp_dir())?;
+		echo.write(|write| write.write_object_properties(&ObjectId::new("Dracula"), vec![(&COUNT, Target::Number(3))]))?;
+	}
+	let roots_path = folder.join("roots.dat");
+	let mut roots = std::fs::read(&roots_path)?;
+	let last = roots.len() - 1;
+	roots[last] ^= 0xff;
+	std::fs::write(&roots_path, &roots)?;
+	let diary_len = std::fs::metadata(folder.join("diary.dat"))?.len();
+	assert!(matches!(Echo::connect(&name, &std::env::temp_dir()), Err(EchoError::Corruption(_))));
+	assert_eq!(std::fs::metadata(folder.join("diary.dat"))?.len(), diary_len);
+	assert_eq!(std::fs::read(&roots_path)?, roots);
+	Ok(())
+}
+
+#[test]
+fn refuse_older_format() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let folder = std::env::temp_dir().join(&name);
+	std::fs::create_dir_all(&folder)?;
+	// Roots written before the header, one 16-byte pair of roots per commit.
+	std::fs::write(folder.join("roots.dat"), [0u8; 16])?;
+	std::fs::write(folder.join("diary.dat"), [7u8; 40])?;
+	assert!(matches!(Echo::connect(&name, &std::env::temp_dir()), Err(EchoError::UnsupportedFormat)));
+	assert_eq!(std::fs::read(folder.join("roots.dat"))?, [0u8; 16]);
+	assert_eq!(std::fs::read(folder.join("diary.dat"))?, [7u8; 40]);
+	Ok(())
+}
+
+#[test]
+fn typed_errors() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.try_number(&dracula, &COUNT)?, 3);
+	assert!(matches!(chamber.try_string(&dracula, &COUNT), Err(EchoError::TypeMismatch { expected: "text", .. })));
+	assert!(matches!(chamber.try_number(&dracula, &MAX_COUNT), Err(EchoError::MissingValue { .. })));
+	assert!(matches!(chamber.try_target_at_object_point(&dracula, &MAX_COUNT), Err(EchoError::MissingValue { .. })));
+	assert_eq!(chamber.try_target_at_object_point_or_none(&dracula, &MAX_COUNT)?, None);
+	let file = std::env::temp_dir().join(unique_name("echo-test-"));
+	std::fs::write(&file, b"not a folder")?;
+	assert!(matches!(Echo::connect("echo", &file), Err(EchoError::Io(_))));
+	let unknown_tag = ObjectId::read_bytes(&mut &[9u8][..]).unwrap_err();
+	assert!(matches!(EchoError::from(unknown_tag), EchoError::Corruption(_)));
+	Ok(())
+}
+
+#[test]
+fn close() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&name, &std::env::temp_dir())?;
+	let clone = echo.clone();
+	clone.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+	echo.close()?;
+	echo.close()?;
+	let error = clone.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(4))])).unwrap_err();
+	assert!(matches!(error, EchoError::WriterGone));
+	assert!(matches!(clone.chamber(), Err(EchoError::WriterGone)));
+	{
+		let echo = Echo::connect(&name, &std::env::temp_dir())?;
+		assert_eq!(echo.chamber()?.number(&dracula, &COUNT), 3);
+		echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(5))]))?;
+	}
+	let echo = Echo::connect(&name, &std::env::temp_dir())?;
+	assert_eq!(echo.chamber()?.number(&dracula, &COUNT), 5);
+	Ok(())
+}
+
+#[test]
+fn connect_shares_writer() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let dracula = ObjectId::new("Dracula");
+	let first = Echo::connect(&name, &std::env::temp_dir())?;
+	let second = Echo::connect(&name, &std::env::temp_dir())?;
+	let synced = EchoOptions::default().durability(Durability::SyncEachCommit);
+	assert!(matches!(Echo::connect_with(&name, &std::env::temp_dir(), synced), Err(EchoError::OptionsMismatch(options)) if options == EchoOptions::default()));
+	first.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+	second.write(|write| write.write_object_properties(&dracula, vec![(&MAX_COUNT, Target::Number(10))]))?;
+	let chamber = first.chamber()?;
+	assert_eq!(chamber.version(), 2);
+	assert_eq!((chamber.number(&dracula, &COUNT), chamber.number(&dracula, &MAX_COUNT)), (3, 10));
+	second.close()?;
+	let third = Echo::connect(&name, &std::env::temp_dir())?;
+	assert_eq!(third.versions()?, 1..=2);
+	assert!(first.versions().is_err());
+	Ok(())
+}
+
+#[test]
+fn reconnect_waits_for_dropped_writer() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	for n in 0..20u64 {
+		let echo = Echo::connect(&name, &std::env::temp_dir())?;
+		echo.write(|write| write.target(Target::Number(n)))?;
+		let dropping = thread::spawn(move || drop(echo));
+		let echo = Echo::connect(&name, &std::env::temp_dir())?;
+		assert_eq!(echo.chamber()?.target_or_none(), Some(Target::Number(n)));
+		dropping.join().unwrap();
+	}
+	Ok(())
+}
+
+#[test]
+fn opening_one_folder_leaves_others_free() -> Result<(), Box<dyn Error>> {
+	let (blocked_name, free_name) = (unique_name("echo-test-"), unique_name("echo-test-"));
+	let blocked_folder = std::env::temp_dir().join(&blocked_name);
+	std::fs::create_dir_all(&blocked_folder)?;
+	let lock_file = OpenOptions::new().write(true).create(true).truncate(false).open(blocked_folder.join("echo.lock"))?;
+	lock_file.lock()?;
+	let options = EchoOptions::default().locking(Locking::Coordinated);
+	let opening = thread::spawn(move || Echo::connect_with(&blocked_name, &std::env::temp_dir(), options).map(|_| ()));
+	thread::sleep(std::time::Duration::from_millis(50));
+	assert!(!opening.is_finished());
+	Echo::connect(&free_name, &std::env::temp_dir())?;
+	lock_file.unlock()?;
+	opening.join().unwrap()?;
+	Ok(())
+}
+
+#[test]
+fn exclusive_lock() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let folder = std::env::temp_dir().join(&name);
+	std::fs::create_dir_all(&folder)?;
+	// A writer in another process holds the folder.
+	let lock_file = OpenOptions::new().write(true).create(true).truncate(false).open(folder.join("echo.lock"))?;
+	lock_file.lock()?;
+	assert!(matches!(Echo::connect(&name, &std::env::temp_dir()), Err(EchoError::Locked)));
+	lock_file.unlock()?;
+	let options = EchoOptions::default().locking(Locking::Coordinated);
+	let echo = Echo::connect_with(&name, &std::env::temp_dir(), options)?;
+	lock_file.lock()?;
+	let echo_thread = echo.clone();
+	let job = thread::spawn(move || echo_thread.write(|write| write.target(Target::Number(1))));
+	thread::sleep(std::time::Duration::from_millis(50));
+	assert!(!job.is_finished());
+	lock_file.unlock()?;
+	job.join().unwrap()?;
+	assert_eq!(echo.versions()?, 1..=1);
+	Ok(())
+}
+
+#[test]
+fn read_only_follows_writer() -> Result<(), Box<dyn Error>> {
+	let name = unique_name("echo-test-");
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&name, &std::env::temp_dir())?;
+	let follower = Echo::open_read_only(&name, &std::env::temp_dir())?;
+	assert_eq!(follower.chamber()?.version(), 0);
+	let chambers = follower.watch(std::time::Duration::from_millis(5));
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+	let chamber = chambers.recv_timeout(std::time::Duration::from_secs(5))?;
+	assert_eq!((chamber.version(), chamber.number(&dracula, &COUNT)), (1, 3));
+	assert_eq!(follower.chamber()?.history(&dracula, &COUNT)?, vec![(1, Some(Target::Number(3)))]);
+	assert!(Echo::open_read_only(&unique_name("echo-test-"), &std::env::temp_dir()).is_err());
+	Ok(())
+}
+
+#[test]
+fn chamber_shared_across_threads() -> Result<(), Box<dyn Error>> {
+	fn assert_send_sync<T: Send + Sync>(_: &T) {}
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	let objects = (0..4).map(|n| ObjectId::String(format!("counter-{}", n))).collect::<Vec<_>>();
+	echo.write(|write| for (n, object) in objects.iter().enumerate() {
+		write.write_object_properties(object, vec![(&COUNT, Target::Number(n as u64))]);
+	})?;
+	let chamber = std::sync::Arc::new(echo.chamber()?);
+	assert_send_sync(&*chamber);
+	let jobs = objects.into_iter().enumerate().map(|(n, object)| {
+		let chamber = chamber.clone();
+		thread::spawn(move || assert_eq!(chamber.number(&object, &COUNT), n as u64))
+	}).collect::<Vec<_>>();
+	for job in jobs {
+		job.join().unwrap();
+	}
+	let clone = (*chamber).clone();
+	assert_eq!(clone.objects_with_point(&COUNT)?.len(), 4);
+	Ok(())
+}
+
+#[test]
+fn cache_counts_hits() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3))]))?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.number(&dracula, &COUNT), 3);
+	let first = echo.cache_stats().unwrap();
+	assert_eq!(chamber.number(&dracula, &COUNT), 3);
+	let second = echo.cache_stats().unwrap();
+	assert!(second.frame_hits > first.frame_hits && second.value_hits > first.value_hits);
+	assert_eq!((second.frame_misses, second.value_misses), (first.frame_misses, first.value_misses));
+	Ok(())
+}
+
+#[test]
+fn large_speech() -> Result<(), Box<dyn Error>> {
+	let dracula = ObjectId::new("Dracula");
+	let points = (0..1000).map(|n| Point::String { aspect: "Counter".into(), name: format!("count-{}", n) }).collect::<Vec<_>>();
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	echo.write(|write| {
+		write.write_object_properties(&dracula, points.iter().map(|point| (point, Target::Number(1))).collect());
+		write.write_object_properties(&dracula, vec![(&points[7], Target::Number(2))]);
+		write.retract(&dracula, &points[9]);
+	})?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.number(&dracula, &points[0]), 1);
+	assert_eq!(chamber.number(&dracula, &points[7]), 2);
+	assert_eq!(chamber.target_at_object_point_or_none(&dracula, &points[9]), None);
+	assert_eq!(chamber.objects_with_point(&points[999])?, vec![dracula.clone()]);
+	assert!(chamber.objects_with_point(&points[9])?.is_empty());
+	Ok(())
+}
+
+#[test]
+fn mixed_load_matches_write() -> Result<(), Box<dyn Error>> {
+	let (dracula, bo_peep) = (ObjectId::new("Dracula"), ObjectId::new("Bo Peep"));
+	let say = |object: &ObjectId, point: &Point, target: Option<u64>| {
+		Say { sayer: Sayer::Unit, object: object.clone(), point: point.clone(), target: target.map(Target::Number) }
+	};
+	let says = vec![
+		say(&dracula, &MAX_COUNT, None),
+		say(&bo_peep, &MAX_COUNT, Some(5)),
+		say(&dracula, &COUNT, Some(4)),
+		say(&bo_peep, &COUNT, None),
+		say(&bo_peep, &COUNT, Some(8)),
+		say(&dracula, &Point::Unit, Some(1)),
+		say(&dracula, &Point::Unit, None),
+	];
+	let loaded = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	let written = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	for echo in [&loaded, &written] {
+		echo.write(|write| {
+			write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(3)), (&MAX_COUNT, Target::Number(10))]);
+			write.write_object_properties(&bo_peep, vec![(&COUNT, Target::Number(7))]);
+		})?;
+	}
+	loaded.load(says.clone())?;
+	written.write(|write| write.says.extend(says.clone()))?;
+	let (loaded, written) = (loaded.chamber()?, written.chamber()?);
+	for point in [&COUNT, &MAX_COUNT, &Point::Unit] {
+		for object in [&dracula, &bo_peep] {
+			assert_eq!(loaded.target_at_object_point_or_none(object, point), written.target_at_object_point_or_none(object, point));
+		}
+		let (mut loaded_objects, mut written_objects) = (loaded.objects_with_point(point)?, written.objects_with_point(point)?);
+		loaded_objects.sort();
+		written_objects.sort();
+		assert_eq!(loaded_objects, written_objects);
+	}
+	assert_eq!(loaded.target_at_object_point_or_none(&bo_peep, &COUNT), Some(Target::Number(8)));
+	assert_eq!(loaded.target_at_object_point_or_none(&dracula, &MAX_COUNT), None);
+	Ok(())
+}
+
+#[test]
+fn load() -> Result<(), Box<dyn Error>> {
+	let echo = Echo::connect(&unique_name("echo-test-"), &std::env::temp_dir())?;
+	let dracula = ObjectId::new("Dracula");
+	echo.write(|write| write.write_object_properties(&dracula, vec![(&COUNT, Target::Number(1))]))?;
+	let objects = (0..5000).map(|n| ObjectId::String(format!("counter-{}", n))).collect::<Vec<_>>();
+	echo.load(objects.iter().enumerate().flat_map(|(n, object)| vec![
+		Say { sayer: Sayer::Unit, object: object.clone(), point: COUNT, target: Some(Target::Number(n as u64)) },
+		Say { sayer: Sayer::Unit, object: object.clone(), point: MAX_COUNT, target: Some(Target::Number(100)) },
+	]))?;
+	echo.write(|write| write.retract(&objects[3], &MAX_COUNT))?;
+	let chamber = echo.chamber()?;
+	assert_eq!(chamber.number(&dracula, &COUNT), 1);
+	assert_eq!(chamber.number(&objects[4999], &COUNT), 4999);
+	assert_eq!(chamber.target_at_object_point_or_none(&objects[3], &MAX_COUNT), None);
+	assert_eq!(chamber.objects_with_point(&COUNT)?.len(), 5001);
+	assert_eq!(chamber.objects_with_point(&MAX_COUNT)?.len(), 4999);
+	assert_eq!(echo.versions()?, 1..=3);
+	Ok(())
+}
@@ -1,6 +1,9 @@
 use std::error::Error;
 use std::hash::{Hash, Hasher};
+use std::io;
+use std::io::Write;
 
+use echo_lib::bytes::WriteBytes;
 use echo_lib::diary::Diary;
 use echo_lib::hamt::{Hamt, Key, Root};
 
@@ -22,6 +25,26 @@ fn write_read() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
+#[test]
+fn remove_read() -> Result<(), Box<dyn Error>> {
+	let (key_a, key_b) = (TestKey { n: 5 }, TestKey { n: 6 });
+	let diary = Diary::temp()?;
+	let mut diary_writer = diary.writer()?;
+	let mut hamt = Hamt::new(Root::ZERO);
+	hamt.write_value(&key_a, &"Hello".to_string(), &mut diary_writer)?;
+	hamt.write_value(&key_b, &"World".to_string(), &mut diary_writer)?;
+	hamt.remove_value(&key_a, &mut diary_writer)?;
+
+	let mut diary_reader = diary_writer.reader()?;
+	let hamt_reader = hamt.reader()?;
+	let value_a: Option<String> = hamt_reader.read_value(&key_a, &mut diary_reader)?;
+	let value_b: Option<String> = hamt_reader.read_value(&key_b, &mut diary_reader)?;
+	assert_eq!((value_a, value_b), (None, Some("World".to_string())));
+	hamt.remove_value(&key_b, &mut diary_writer)?;
+	assert_eq!(hamt.root, Root::ZERO);
+	Ok(())
+}
+
 #[test]
 fn read_none_from_empty_diary() -> Result<(), Box<dyn Error>> {
 	let key = TestKey { n: 5 };
@@ -42,4 +65,8 @@ impl Hash for TestKey {
 	fn hash<H: Hasher>(&self, state: &mut H) { state.write_u32(self.n) }
 }
 
+impl WriteBytes for TestKey {
+	fn write_bytes(&self, writer: &mut impl Write) -> io::Result<usize> { self.n.write_bytes(writer) }
+}
+
 impl Key for TestKey {}
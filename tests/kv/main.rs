@@ -27,6 +27,17 @@ fn it_works() -> Result<(), Box<dyn Error>> {
 	Ok(())
 }
 
+#[test]
+fn remove() -> Result<(), Box<dyn Error>> {
+	let equation = Equation { left: 3, right: "3".to_string() };
+	let kvs = kv::open("removals", &temp_dir("kv-test")?)?;
+	kvs.write(&equation, &Difficulty::Easy)?;
+	kvs.remove(&equation)?;
+	let stored_difficulty = kvs.catalog()?.read(&equation, || Difficulty::Hard)?;
+	assert_eq!(stored_difficulty, Difficulty::Hard);
+	Ok(())
+}
+
 #[derive(Debug, Hash)]
 struct Equation {
 	left: u64,